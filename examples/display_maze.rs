@@ -3,6 +3,6 @@ use std::fs;
 
 pub fn main() {
     let data = fs::read_to_string("maze.txt").expect("Unable to read file");
-    let maze = Maze::load_from_str(&data);
+    let maze = Maze::load_from_str(&data).expect("Unable to parse maze");
     println!("{}", maze);
 }
@@ -0,0 +1,179 @@
+#![forbid(unsafe_code)]
+
+use crate::types::*;
+
+pub const MAX_ENUMERATION_SIZE: u8 = 4;
+
+/// Iterates over every perfect maze (spanning tree) of the `size` x `size` region at the origin.
+/// The region is surrounded by walls; cells outside of it are left open.
+#[derive(Debug, Clone)]
+pub struct PerfectMazes {
+    size: u8,
+    start: CoordXY,
+    goal: CoordXY,
+    // Bit set of the open interior walls of the next candidate
+    mask: Option<u32>,
+}
+
+pub fn perfect_mazes(size: u8, start: CoordXY, goal: CoordXY) -> Result<PerfectMazes, Error> {
+    if size == 0 || size > MAX_ENUMERATION_SIZE || size as usize > WIDTH {
+        return Err(Error::OutOfRange);
+    }
+    let tree_edges = size as u32 * size as u32 - 1;
+    Ok(PerfectMazes {
+        size,
+        start,
+        goal,
+        mask: Some((1u32 << tree_edges) - 1),
+    })
+}
+
+impl PerfectMazes {
+    #[inline]
+    fn edge_count(&self) -> u32 {
+        2 * self.size as u32 * (self.size as u32 - 1)
+    }
+    // Interior walls are numbered east walls first, then north walls, both in row-major order
+    fn edge(&self, i: u32) -> (CoordXY, Direction) {
+        let n = self.size as u32;
+        let half = n * (n - 1);
+        if i < half {
+            let coord = CoordXY::new((i % (n - 1)) as u8, (i / (n - 1)) as u8).unwrap();
+            (coord, Direction::East)
+        } else {
+            let i = i - half;
+            let coord = CoordXY::new((i % n) as u8, (i / n) as u8).unwrap();
+            (coord, Direction::North)
+        }
+    }
+    fn is_spanning_tree(&self, mask: u32) -> bool {
+        let n = self.size as usize;
+        let mut parents = [0usize; (MAX_ENUMERATION_SIZE * MAX_ENUMERATION_SIZE) as usize];
+        for (i, p) in parents.iter_mut().enumerate() {
+            *p = i;
+        }
+        fn root(parents: &mut [usize], mut i: usize) -> usize {
+            while parents[i] != i {
+                parents[i] = parents[parents[i]];
+                i = parents[i];
+            }
+            i
+        }
+        for i in (0..self.edge_count()).filter(|i| mask & (1 << i) != 0) {
+            let (coord, direction) = self.edge(i);
+            let next = (coord + direction.into()).unwrap();
+            let a = root(
+                &mut parents,
+                coord.x().value() as usize + coord.y().value() as usize * n,
+            );
+            let b = root(
+                &mut parents,
+                next.x().value() as usize + next.y().value() as usize * n,
+            );
+            if a == b {
+                return false;
+            }
+            parents[a] = b;
+        }
+        // NOTE: an acyclic graph with n * n - 1 edges over n * n nodes is a spanning tree
+        true
+    }
+    fn build(&self, mask: u32) -> Maze {
+        let mut maze = Maze::new(self.start, self.goal);
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let coord = CoordXY::new(x, y).unwrap();
+                maze.set_cell_state(coord, Direction::North, true);
+                maze.set_cell_state(coord, Direction::East, true);
+            }
+        }
+        for i in (0..self.edge_count()).filter(|i| mask & (1 << i) != 0) {
+            let (coord, direction) = self.edge(i);
+            maze.set_cell_state(coord, direction, false);
+        }
+        maze
+    }
+    // Next bit set with the same number of bits (Gosper's hack)
+    fn next_mask(&self, mask: u32) -> Option<u32> {
+        if mask == 0 {
+            return None;
+        }
+        let c = mask & mask.wrapping_neg();
+        let r = mask + c;
+        let next = (((r ^ mask) >> 2) / c) | r;
+        (next < 1 << self.edge_count()).then_some(next)
+    }
+}
+
+impl Iterator for PerfectMazes {
+    type Item = Maze;
+    fn next(&mut self) -> Option<Maze> {
+        while let Some(mask) = self.mask {
+            self.mask = self.next_mask(mask);
+            if self.is_spanning_tree(mask) {
+                return Some(self.build(mask));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::four_way_grid::Graph;
+    use crate::solver::astar::astar;
+
+    #[test]
+    fn perfect_mazes_2x2() {
+        let start = CoordXY::new(0, 0).unwrap();
+        let goal = CoordXY::new(1, 1).unwrap();
+        let mut count = 0;
+        for maze in perfect_mazes(2, start, goal).unwrap() {
+            let graph = Graph { maze };
+            let route = astar(
+                &graph,
+                Graph::node_index_by_coord_xy(start).unwrap(),
+                Graph::node_index_by_coord_xy(goal).unwrap(),
+            )
+            .unwrap();
+            assert_eq!(route.cost(), 2);
+            count += 1;
+        }
+        // A 4-cycle has four spanning trees
+        assert_eq!(count, 4);
+    }
+    #[test]
+    fn perfect_mazes_3x3() {
+        let start = CoordXY::new(0, 0).unwrap();
+        let goal = CoordXY::new(2, 2).unwrap();
+        let mut count = 0;
+        for maze in perfect_mazes(3, start, goal).unwrap() {
+            let graph = Graph { maze };
+            let route = astar(
+                &graph,
+                Graph::node_index_by_coord_xy(start).unwrap(),
+                Graph::node_index_by_coord_xy(goal).unwrap(),
+            )
+            .unwrap();
+            assert!(route.cost() >= 4);
+            assert!(route.nodes().iter().all(|n| {
+                let location = n.to_agent_state(None).location;
+                location.x().value() < 3 && location.y().value() < 3
+            }));
+            count += 1;
+        }
+        assert_eq!(count, 192);
+    }
+    #[test]
+    fn perfect_mazes_1x1() {
+        let coord = CoordXY::new(0, 0).unwrap();
+        assert_eq!(perfect_mazes(1, coord, coord).unwrap().count(), 1);
+    }
+    #[test]
+    fn perfect_mazes_out_of_range() {
+        let coord = CoordXY::new(0, 0).unwrap();
+        assert!(perfect_mazes(0, coord, coord).is_err());
+        assert!(perfect_mazes(MAX_ENUMERATION_SIZE + 1, coord, coord).is_err());
+    }
+}
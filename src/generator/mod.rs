@@ -0,0 +1,310 @@
+#![forbid(unsafe_code)]
+
+use rand_core::RngCore;
+
+use crate::graph::{four_way_grid::Graph, Cost};
+use crate::solver::astar::astar;
+use crate::types::*;
+
+#[cfg(feature = "std")]
+pub mod enumerate;
+
+pub const MAX_GENERATION_RETRIES: usize = 64;
+
+/// Generates a perfect maze by carving passages with a randomized depth-first search from
+/// `start`, so that every cell is reachable from every other one through a single route.
+pub fn generate_backtracker(rng: &mut impl RngCore, start: CoordXY, goal: CoordXY) -> Maze {
+    let mut maze = walled_maze(start, goal);
+    let mut visited = [false; WIDTH * WIDTH];
+    let mut stack = Vec::<CoordXY, { WIDTH * WIDTH }>::new();
+    visited[cell_index(start)] = true;
+    stack.push(start).unwrap();
+    while let Some(&current) = stack.last() {
+        let mut candidates = Vec::<(Direction, CoordXY), 4>::new();
+        for direction in [
+            Direction::North,
+            Direction::East,
+            Direction::South,
+            Direction::West,
+        ] {
+            if let Ok(next) = current + direction.into() {
+                if !visited[cell_index(next)] {
+                    candidates.push((direction, next)).unwrap();
+                }
+            }
+        }
+        if candidates.is_empty() {
+            stack.pop();
+            continue;
+        }
+        let (direction, next) = candidates[rng.next_u32() as usize % candidates.len()];
+        maze.set_cell_state(current, direction, false);
+        visited[cell_index(next)] = true;
+        // NOTE: every cell is pushed at most once
+        stack.push(next).unwrap();
+    }
+    maze
+}
+
+/// Generates a perfect maze with randomized Prim's algorithm, which grows the maze from `start`
+/// by opening a random wall on its boundary at a time. Compared to `generate_backtracker` the
+/// result branches more and has shorter dead ends.
+pub fn generate_prim(rng: &mut impl RngCore, start: CoordXY, goal: CoordXY) -> Maze {
+    let mut maze = walled_maze(start, goal);
+    let mut in_maze = [false; WIDTH * WIDTH];
+    // NOTE: walls from a cell in the maze to one outside it, each of which is added at most once
+    let mut frontier = Vec::<(CoordXY, Direction), { 2 * WIDTH * WIDTH }>::new();
+    let add_cell =
+        |coord: CoordXY, in_maze: &mut [bool; WIDTH * WIDTH], frontier: &mut Vec<_, _>| {
+            in_maze[cell_index(coord)] = true;
+            use Direction::*;
+            for direction in [North, East, South, West] {
+                if let Ok(next) = coord + direction.into() {
+                    if !in_maze[cell_index(next)] {
+                        frontier.push((coord, direction)).unwrap();
+                    }
+                }
+            }
+        };
+    add_cell(start, &mut in_maze, &mut frontier);
+    while !frontier.is_empty() {
+        let (coord, direction) = frontier.swap_remove(rng.next_u32() as usize % frontier.len());
+        // NOTE: walls in the frontier are never on the outer boundary
+        let next = (coord + direction.into()).unwrap();
+        if in_maze[cell_index(next)] {
+            continue;
+        }
+        maze.set_cell_state(coord, direction, false);
+        add_cell(next, &mut in_maze, &mut frontier);
+    }
+    maze
+}
+
+// Maze with every wall closed
+fn walled_maze(start: CoordXY, goal: CoordXY) -> Maze {
+    let mut maze = Maze::new(start, goal);
+    for y in 0..WIDTH as u8 {
+        for x in 0..WIDTH as u8 {
+            let coord = CoordXY::new(x, y).unwrap();
+            maze.set_cell_state(coord, Direction::North, true);
+            maze.set_cell_state(coord, Direction::East, true);
+        }
+    }
+    maze
+}
+
+impl Maze {
+    /// Generates mazes until the optimal route from `start` to `goal` costs at least `min_len`.
+    /// Gives up with `Error::GenerationFailed` after `MAX_GENERATION_RETRIES` attempts.
+    pub fn generate_min_length<R: RngCore>(
+        rng: &mut R,
+        start: CoordXY,
+        goal: CoordXY,
+        min_len: Cost,
+    ) -> Result<Maze, Error> {
+        for _ in 0..MAX_GENERATION_RETRIES {
+            let graph = Graph {
+                maze: generate_backtracker(rng, start, goal),
+            };
+            if optimal_cost(&graph, start, goal)? >= min_len {
+                return Ok(graph.maze);
+            }
+        }
+        Err(Error::GenerationFailed)
+    }
+    /// Generates a perfect maze and then opens `loops` more walls between neighboring cells, each
+    /// of which closes exactly one new cycle. The number of loops is capped at the number of
+    /// interior walls left in a perfect maze, i.e. `(WIDTH - 1) * (WIDTH - 1)`.
+    pub fn generate_with_loops<R: RngCore>(
+        rng: &mut R,
+        start: CoordXY,
+        goal: CoordXY,
+        loops: usize,
+    ) -> Maze {
+        let mut maze = generate_backtracker(rng, start, goal);
+        // NOTE: all cells are connected already, so opening any interior wall adds a cycle
+        for _ in 0..loops.min((WIDTH - 1) * (WIDTH - 1)) {
+            loop {
+                let i = rng.next_u32() as usize % (WIDTH * WIDTH);
+                let coord = CoordXY::new((i % WIDTH) as u8, (i / WIDTH) as u8).unwrap();
+                let direction = if rng.next_u32().is_multiple_of(2) {
+                    Direction::North
+                } else {
+                    Direction::East
+                };
+                if (coord + direction.into()).is_ok()
+                    && maze.cell(coord).state_by_direction(direction)
+                {
+                    maze.set_cell_state(coord, direction, false);
+                    break;
+                }
+            }
+        }
+        maze
+    }
+}
+
+fn optimal_cost(graph: &Graph, start: CoordXY, goal: CoordXY) -> Result<Cost, Error> {
+    astar(
+        graph,
+        Graph::node_index_by_coord_xy(start)?,
+        Graph::node_index_by_coord_xy(goal)?,
+    )
+    .map(|route| route.cost())
+    .ok_or(Error::GenerationFailed)
+}
+
+#[inline]
+fn cell_index(coord: CoordXY) -> usize {
+    coord.x().value() as usize + coord.y().value() as usize * WIDTH
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "std")] {
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        pub struct Stats {
+            pub min: Cost,
+            pub max: Cost,
+            pub mean: f64,
+        }
+
+        /// Generates `trials` random mazes from the bottom-left corner to the center and
+        /// aggregates their optimal route lengths. Returns `None` if `trials` is zero.
+        pub fn route_length_stats<R: RngCore>(rng: &mut R, trials: usize) -> Option<Stats> {
+            let start = CoordXY::new(0, 0).unwrap();
+            let goal = CoordXY::new(WIDTH as u8 / 2, WIDTH as u8 / 2).unwrap();
+            if trials == 0 {
+                return None;
+            }
+            let mut stats = Stats {
+                min: Cost::MAX,
+                max: 0,
+                mean: 0.0,
+            };
+            let mut sum: Cost = 0;
+            for _ in 0..trials {
+                let graph = Graph {
+                    maze: generate_backtracker(rng, start, goal),
+                };
+                // NOTE: generated mazes are fully connected
+                let cost = optimal_cost(&graph, start, goal).unwrap();
+                stats.min = stats.min.min(cost);
+                stats.max = stats.max.max(cost);
+                sum += cost;
+            }
+            stats.mean = sum as f64 / trials as f64;
+            Some(stats)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_pcg::Pcg32;
+
+    fn open_walls(maze: &Maze) -> usize {
+        let mut open_walls = 0;
+        for y in 0..WIDTH as u8 {
+            for x in 0..WIDTH as u8 {
+                let cell = maze.cell(CoordXY::new(x, y).unwrap());
+                open_walls += !cell.north() as usize + !cell.east() as usize;
+            }
+        }
+        open_walls
+    }
+
+    #[test]
+    fn generate_backtracker_connects_all_cells() {
+        let mut rng = Pcg32::new(0xcafef00dd15ea5e5, 0xa02bdbf7bb3c0a7);
+        let maze = generate_backtracker(
+            &mut rng,
+            CoordXY::new(0, 0).unwrap(),
+            CoordXY::new(7, 7).unwrap(),
+        );
+        // A spanning tree over all cells
+        assert_eq!(open_walls(&maze), WIDTH * WIDTH - 1);
+    }
+    #[test]
+    fn generate_backtracker_valid_across_seeds() {
+        let start = CoordXY::new(0, 0).unwrap();
+        let goal = CoordXY::new(WIDTH as u8 / 2, WIDTH as u8 / 2).unwrap();
+        for seed in 0..16 {
+            let mut rng = Pcg32::new(seed, 0xa02bdbf7bb3c0a7);
+            let maze = generate_backtracker(&mut rng, start, goal);
+            assert_eq!(maze.validate(), Ok(()));
+            assert!(maze.is_reachable(start, goal));
+            assert_eq!(open_walls(&maze), WIDTH * WIDTH - 1);
+        }
+    }
+    #[test]
+    fn generate_prim_perfect() {
+        let start = CoordXY::new(0, 0).unwrap();
+        let goal = CoordXY::new(WIDTH as u8 / 2, WIDTH as u8 / 2).unwrap();
+        for seed in 0..4 {
+            let mut rng = Pcg32::new(seed, 0xa02bdbf7bb3c0a7);
+            let graph = Graph {
+                maze: generate_prim(&mut rng, start, goal),
+            };
+            assert_eq!(graph.maze.validate(), Ok(()));
+            // A spanning tree over all cells
+            assert_eq!(open_walls(&graph.maze), WIDTH * WIDTH - 1);
+            assert_eq!(graph.maze.reachable_cells(start).len(), WIDTH * WIDTH);
+            assert_eq!(graph.maze.route_count(&graph), 1);
+        }
+    }
+    #[test]
+    fn generate_with_loops() {
+        let mut rng = Pcg32::new(0xcafef00dd15ea5e5, 0xa02bdbf7bb3c0a7);
+        let start = CoordXY::new(0, 0).unwrap();
+        let goal = CoordXY::new(7, 7).unwrap();
+        let graph = Graph {
+            maze: Maze::generate_with_loops(&mut rng, start, goal, 0),
+        };
+        assert_eq!(open_walls(&graph.maze), WIDTH * WIDTH - 1);
+        assert_eq!(graph.maze.route_count(&graph), 1);
+
+        for loops in [1, 2, 5] {
+            let graph = Graph {
+                maze: Maze::generate_with_loops(&mut rng, start, goal, loops),
+            };
+            // Each loop opens one wall on top of a spanning tree
+            assert_eq!(open_walls(&graph.maze), WIDTH * WIDTH - 1 + loops);
+            // Every route is the tree route combined with a subset of the loops
+            let count = graph.maze.route_count(&graph);
+            assert!(count >= 1 && count <= 1 << loops);
+        }
+    }
+    #[test]
+    fn generate_min_length() {
+        let mut rng = Pcg32::new(0xcafef00dd15ea5e5, 0xa02bdbf7bb3c0a7);
+        let start = CoordXY::new(0, 0).unwrap();
+        let goal = CoordXY::new(1, 0).unwrap();
+        let min_len = 3 * WIDTH as Cost;
+        let graph = Graph {
+            maze: Maze::generate_min_length(&mut rng, start, goal, min_len).unwrap(),
+        };
+        assert!(optimal_cost(&graph, start, goal).unwrap() >= min_len);
+    }
+    #[test]
+    fn generate_min_length_unsatisfiable() {
+        let mut rng = Pcg32::new(0xcafef00dd15ea5e5, 0xa02bdbf7bb3c0a7);
+        let result = Maze::generate_min_length(
+            &mut rng,
+            CoordXY::new(0, 0).unwrap(),
+            CoordXY::new(1, 0).unwrap(),
+            (WIDTH * WIDTH) as Cost,
+        );
+        assert_eq!(result.err(), Some(Error::GenerationFailed));
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn route_length_stats_consistent() {
+        let mut rng = Pcg32::new(0xcafef00dd15ea5e5, 0xa02bdbf7bb3c0a7);
+        let stats = route_length_stats(&mut rng, 5).unwrap();
+        assert!(stats.min as f64 <= stats.mean);
+        assert!(stats.mean <= stats.max as f64);
+        assert!(stats.min >= WIDTH as Cost);
+        assert!(route_length_stats(&mut rng, 0).is_none());
+    }
+}
@@ -0,0 +1,243 @@
+#![forbid(unsafe_code)]
+
+use crate::graph::*;
+
+/// Cost of a straight move in `EightWayGrid`, i.e. its `COST_SCALE`.
+pub const ORTHOGONAL_COST: Cost = 10;
+/// Cost of a diagonal move in `EightWayGrid`, i.e. `ORTHOGONAL_COST` times the square root of two
+/// rounded down.
+pub const DIAGONAL_COST: Cost = 14;
+
+// The eight unit steps, clockwise from north
+const STEPS: [(i8, i8); 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+
+/// Grid of cells where the agent can also move diagonally, cutting the corner between two
+/// orthogonal neighbors when all four walls around that corner are open. Node indices are shared
+/// with `four_way_grid`.
+#[derive(Debug)]
+pub struct EightWayGrid {
+    pub maze: Maze,
+}
+pub type Graph = EightWayGrid;
+impl EightWayGrid {
+    fn coord_xy_by_node_index(index: NodeIndex<Self>) -> Result<CoordXY, Error> {
+        let x = (index.value % WIDTH as NodeIndexValue) as u8;
+        let y = (index.value / WIDTH as NodeIndexValue) as u8;
+        CoordXY::new(x, y)
+    }
+    fn vector_xy_by_node_index_pair(from: NodeIndex<Self>, to: NodeIndex<Self>) -> VectorXY {
+        Self::coord_xy_by_node_index(to).unwrap() - Self::coord_xy_by_node_index(from).unwrap()
+    }
+    pub fn node_index_by_coord_xy(coord: CoordXY) -> Result<NodeIndex<Self>, Error> {
+        NodeIndex::new(
+            coord.x().value() as NodeIndexValue
+                + coord.y().value() as NodeIndexValue * WIDTH as NodeIndexValue,
+        )
+    }
+    // Returns whether the agent can move from `from` by `vector`, which is one of the eight unit
+    // steps
+    fn is_passable(&self, from: CoordXY, vector: VectorXY) -> bool {
+        let horizontal = VectorXY { x: vector.x, y: 0 };
+        let vertical = VectorXY { x: 0, y: vector.y };
+        let cell = self.maze.cell(from);
+        match (
+            Direction::try_from(horizontal),
+            Direction::try_from(vertical),
+        ) {
+            (Ok(east_west), Ok(north_south)) => {
+                // NOTE: the diagonal neighbor is in range whenever both orthogonal ones are
+                let (Ok(beside), Ok(above)) = (from + horizontal, from + vertical) else {
+                    return false;
+                };
+                !cell.state_by_direction(east_west)
+                    && !cell.state_by_direction(north_south)
+                    && !self.maze.cell(beside).state_by_direction(north_south)
+                    && !self.maze.cell(above).state_by_direction(east_west)
+            }
+            _ => {
+                Direction::try_from(vector)
+                    .is_ok_and(|direction| !cell.state_by_direction(direction))
+                    && (from + vector).is_ok()
+            }
+        }
+    }
+}
+impl SharedIndexSpace<four_way_grid::Graph> for EightWayGrid {}
+impl SharedIndexSpace<EightWayGrid> for four_way_grid::Graph {}
+impl GraphBase for EightWayGrid {
+    const MAX_NODE_INDEX: NodeIndexValue = WIDTH as NodeIndexValue * WIDTH as NodeIndexValue - 1;
+    const COST_SCALE: Cost = ORTHOGONAL_COST;
+    fn cost(from: NodeIndex<Self>, to: NodeIndex<Self>) -> Cost {
+        Self::optimistic_cost(from, to)
+    }
+    fn optimistic_cost(from: NodeIndex<Self>, to: NodeIndex<Self>) -> Cost {
+        // NOTE: octile distance, i.e. as many diagonal moves as possible and straight ones for
+        // the rest
+        let vector = Self::vector_xy_by_node_index_pair(from, to);
+        let (dx, dy) = (
+            vector.x.unsigned_abs() as Cost,
+            vector.y.unsigned_abs() as Cost,
+        );
+        DIAGONAL_COST * dx.min(dy) + ORTHOGONAL_COST * dx.abs_diff(dy) as Cost
+    }
+    fn agent_state_by_node_index(
+        index: NodeIndex<Self>,
+        from_index: Option<NodeIndex<Self>>,
+    ) -> AgentState {
+        AgentState {
+            location: Self::coord_xy_by_node_index(index).unwrap(),
+            local_location: CellLocalLocation::Center,
+            heading_vector: from_index.map_or(VectorXY { x: 0, y: 0 }, |from_index| {
+                Self::vector_xy_by_node_index_pair(from_index, index)
+            }),
+        }
+    }
+    fn node_index_by_agent_state(agent_state: AgentState) -> Result<NodeIndex<Self>, Error> {
+        if agent_state.local_location != CellLocalLocation::Center {
+            return Err(Error::InvalidLocation);
+        }
+        Self::node_index_by_coord_xy(agent_state.location)
+    }
+    /// Every 45 degrees of turning costs half a straight move, i.e. as much as a quarter turn on
+    /// `four_way_grid` relative to its moves.
+    fn turn_cost(from_heading: VectorXY, edge: &Edge<Self>) -> Cost {
+        let step = |vector: VectorXY| {
+            STEPS
+                .iter()
+                .position(|&step| step == (vector.x.signum(), vector.y.signum()))
+        };
+        let (Some(a), Some(b)) = (
+            step(from_heading),
+            step(Self::vector_xy_by_node_index_pair(edge.from(), edge.to())),
+        ) else {
+            return 0;
+        };
+        let eighths = a.abs_diff(b).min(8 - a.abs_diff(b));
+        eighths as Cost * ORTHOGONAL_COST / 2
+    }
+    fn edge(&self, from: NodeIndex<Self>, to: NodeIndex<Self>) -> Option<Edge<Self>> {
+        let vector = Self::vector_xy_by_node_index_pair(from, to);
+        let is_step =
+            vector.x.abs() <= 1 && vector.y.abs() <= 1 && vector != VectorXY { x: 0, y: 0 };
+        (is_step && self.is_passable(Self::coord_xy_by_node_index(from).ok()?, vector))
+            .then(|| Edge::new(from, to))
+    }
+    fn neighbors(&self, from: NodeIndex<Self>) -> Vec<Edge<Self>, MAX_NEIGHBORS> {
+        let location = Self::coord_xy_by_node_index(from).unwrap();
+        let mut vec = Vec::<Edge<Self>, MAX_NEIGHBORS>::new();
+        for (x, y) in STEPS {
+            let vector = VectorXY { x, y };
+            if !self.is_passable(location, vector) {
+                continue;
+            }
+            // NOTE: passable steps stay in the grid, and there are eight of them at most
+            let to = Self::node_index_by_coord_xy((location + vector).unwrap()).unwrap();
+            vec.push(Edge::new(from, to)).unwrap();
+        }
+        vec
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::four_way_grid::index;
+    use crate::solver::astar::astar;
+
+    fn open_grid() -> Graph {
+        Graph {
+            maze: Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(7, 7).unwrap()),
+        }
+    }
+
+    #[test]
+    fn cost() {
+        assert_eq!(Graph::cost(index(1, 1), index(2, 1)), ORTHOGONAL_COST);
+        assert_eq!(Graph::cost(index(1, 1), index(2, 2)), DIAGONAL_COST);
+        assert_eq!(Graph::optimistic_cost(index(0, 0), index(3, 1)), 34);
+        assert_eq!(Graph::optimistic_cost(index(3, 1), index(0, 0)), 34);
+    }
+    #[test]
+    fn turn_cost() {
+        let east = Edge::new(index(1, 1), index(2, 1));
+        assert_eq!(Graph::turn_cost(VectorXY { x: 0, y: 0 }, &east), 0);
+        assert_eq!(Graph::turn_cost(VectorXY { x: 1, y: 0 }, &east), 0);
+        assert_eq!(Graph::turn_cost(VectorXY { x: 1, y: 1 }, &east), 5);
+        assert_eq!(Graph::turn_cost(VectorXY { x: 0, y: -1 }, &east), 10);
+        assert_eq!(Graph::turn_cost(VectorXY { x: -1, y: -1 }, &east), 15);
+        assert_eq!(Graph::turn_cost(VectorXY { x: -1, y: 0 }, &east), 20);
+        let north_west = Edge::new(index(1, 1), index(0, 2));
+        assert_eq!(Graph::turn_cost(VectorXY { x: 0, y: 1 }, &north_west), 5);
+        assert_eq!(Graph::turn_cost(VectorXY { x: 1, y: 1 }, &north_west), 10);
+    }
+    #[test]
+    fn neighbors_open_cell() {
+        let g = open_grid();
+        assert_eq!(g.neighbors(index(3, 3)).len(), 8);
+        // Corners and sides of the maze
+        assert_eq!(g.neighbors(index(0, 0)).len(), 3);
+        assert_eq!(g.neighbors(index(3, 0)).len(), 5);
+    }
+    #[test]
+    fn neighbors_blocked_diagonal() {
+        let mut g = open_grid();
+        // A wall touching the north-east corner of (3, 3) from the neighboring cell only
+        g.maze
+            .set_cell_state(CoordXY::new(4, 3).unwrap(), Direction::North, true);
+        let neighbors = g.neighbors(index(3, 3));
+        assert_eq!(neighbors.len(), 7);
+        assert!(neighbors.iter().all(|edge| edge.to() != index(4, 4)));
+        assert!(g.edge(index(3, 3), index(4, 4)).is_none());
+        assert!(g.edge(index(4, 4), index(3, 3)).is_none());
+        assert_eq!(
+            g.edge(index(3, 3), index(2, 4)).map(|edge| edge.cost()),
+            Some(DIAGONAL_COST)
+        );
+
+        // A wall of the cell itself blocks both diagonals next to it
+        g.maze
+            .set_cell_state(CoordXY::new(3, 3).unwrap(), Direction::West, true);
+        assert_eq!(g.neighbors(index(3, 3)).len(), 4);
+    }
+    #[test]
+    fn edge_not_adjacent() {
+        let g = open_grid();
+        assert!(g.edge(index(3, 3), index(3, 3)).is_none());
+        assert!(g.edge(index(3, 3), index(5, 3)).is_none());
+        // Consecutive indices across the end of a row are not adjacent
+        let last = WIDTH as u8 - 1;
+        assert!(g.edge(index(last, 2), index(0, 3)).is_none());
+    }
+    #[test]
+    fn shared_index_space() {
+        let node = index(5, 2);
+        let four_way: NodeIndex<four_way_grid::Graph> = node.transmute_to();
+        assert_eq!(
+            four_way.to_agent_state(None).location,
+            node.to_agent_state(None).location
+        );
+        assert_eq!(four_way.transmute_to::<Graph>(), node);
+    }
+    #[test]
+    fn astar_diagonal() {
+        let g = open_grid();
+        let route = astar(&g, index(0, 0), index(3, 3)).unwrap();
+        assert_eq!(route.cost(), 3 * DIAGONAL_COST);
+        assert_eq!(
+            route.nodes(),
+            &[index(0, 0), index(1, 1), index(2, 2), index(3, 3)]
+        );
+        let route = astar(&g, index(0, 0), index(4, 2)).unwrap();
+        assert_eq!(route.cost(), 2 * DIAGONAL_COST + 2 * ORTHOGONAL_COST);
+        assert_eq!(route.nodes().len(), 5);
+    }
+}
@@ -0,0 +1,152 @@
+#![forbid(unsafe_code)]
+
+use crate::graph::*;
+
+/// A view of `base` for planning during exploration, which also lets the agent pass through the
+/// walls whose check bits are not set in `maze`. A move is only blocked when the wall in the way
+/// has been sensed and is present, so unexplored walls are assumed to be open.
+pub struct ExplorationGrid<'a, T: GraphBase> {
+    pub maze: &'a Maze,
+    pub base: &'a T,
+}
+/// Exploration view of the four-way grid over the same maze.
+pub type Graph<'a> = ExplorationGrid<'a, four_way_grid::Graph>;
+impl<'a, T: GraphBase> ExplorationGrid<'a, T> {
+    pub fn new(maze: &'a Maze, base: &'a T) -> Self {
+        Self { maze, base }
+    }
+    #[inline]
+    fn to_base(index: NodeIndex<Self>) -> NodeIndex<T> {
+        index.transmute_to()
+    }
+    #[inline]
+    fn from_base(index: NodeIndex<T>) -> NodeIndex<Self> {
+        index.transmute_to()
+    }
+    // Node beyond an unchecked wall of the cell of `from`
+    fn unchecked_neighbor(
+        &self,
+        from: NodeIndex<Self>,
+        direction: Direction,
+    ) -> Option<NodeIndex<Self>> {
+        let location = from.to_agent_state(None).location;
+        if self.maze.cell(location).check_by_direction(direction) {
+            return None;
+        }
+        node_index_at((location + direction.into()).ok()?).ok()
+    }
+}
+impl<T: GraphBase> SharedIndexSpace<T> for ExplorationGrid<'_, T> {}
+impl<T: GraphBase> SharedIndexSpace<ExplorationGrid<'_, T>> for T {}
+impl<T: GraphBase> GraphBase for ExplorationGrid<'_, T> {
+    const MAX_NODE_INDEX: NodeIndexValue = T::MAX_NODE_INDEX;
+    const COST_SCALE: Cost = T::COST_SCALE;
+    fn cost(from: NodeIndex<Self>, to: NodeIndex<Self>) -> Cost {
+        T::cost(Self::to_base(from), Self::to_base(to))
+    }
+    fn optimistic_cost(from: NodeIndex<Self>, to: NodeIndex<Self>) -> Cost {
+        T::optimistic_cost(Self::to_base(from), Self::to_base(to))
+    }
+    fn agent_state_by_node_index(
+        index: NodeIndex<Self>,
+        from_index: Option<NodeIndex<Self>>,
+    ) -> AgentState {
+        T::agent_state_by_node_index(Self::to_base(index), from_index.map(Self::to_base))
+    }
+    fn node_index_by_agent_state(agent_state: AgentState) -> Result<NodeIndex<Self>, Error> {
+        T::node_index_by_agent_state(agent_state).map(Self::from_base)
+    }
+    fn turn_cost(from_heading: VectorXY, edge: &Edge<Self>) -> Cost {
+        T::turn_cost(
+            from_heading,
+            &Edge::new(Self::to_base(edge.from()), Self::to_base(edge.to())),
+        )
+    }
+    fn neighbors(&self, from: NodeIndex<Self>) -> Vec<Edge<Self>, MAX_NEIGHBORS> {
+        let mut vec: Vec<Edge<Self>, MAX_NEIGHBORS> = self
+            .base
+            .neighbors(Self::to_base(from))
+            .iter()
+            .map(|edge| Edge::new(from, Self::from_base(edge.to())))
+            .collect();
+        for direction in Direction::iter() {
+            if let Some(to) = self.unchecked_neighbor(from, direction) {
+                if !vec.iter().any(|e| e.to() == to) {
+                    // NOTE: the orthogonal neighbors are a subset of all possible neighbors
+                    vec.push(Edge::new(from, to)).ok();
+                }
+            }
+        }
+        vec
+    }
+    fn edge(&self, from: NodeIndex<Self>, to: NodeIndex<Self>) -> Option<Edge<Self>> {
+        if self
+            .base
+            .edge(Self::to_base(from), Self::to_base(to))
+            .is_some()
+        {
+            return Some(Edge::new(from, to));
+        }
+        let vector = to.to_agent_state(None).location - from.to_agent_state(None).location;
+        let direction = Direction::try_from(vector).ok()?;
+        (self.unchecked_neighbor(from, direction) == Some(to)).then(|| Edge::new(from, to))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::four_way_grid::index;
+    use crate::solver::astar::astar;
+
+    // Four-way grid with every wall closed, none of which has been sensed
+    fn walled_grid() -> four_way_grid::Graph {
+        let mut maze = Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(3, 3).unwrap());
+        for y in 0..WIDTH as u8 {
+            for x in 0..WIDTH as u8 {
+                let coord = CoordXY::new(x, y).unwrap();
+                maze.set_cell_state(coord, Direction::North, true);
+                maze.set_cell_state(coord, Direction::East, true);
+            }
+        }
+        four_way_grid::Graph { maze }
+    }
+
+    #[test]
+    fn neighbors_unexplored() {
+        let base = walled_grid();
+        let g = Graph::new(&base.maze, &base);
+        assert!(base.neighbors(index(2, 2)).is_empty());
+        assert_eq!(g.neighbors(index(2, 2)).len(), 4);
+        // Steps off the grid are never taken
+        assert_eq!(g.neighbors(index(0, 0)).len(), 2);
+    }
+    #[test]
+    fn neighbors_sensed() {
+        let mut base = walled_grid();
+        base.maze
+            .sense(CoordXY::new(2, 2).unwrap(), [true, false, true, true]);
+        let g = Graph::new(&base.maze, &base);
+        let neighbors = g.neighbors(index(2, 2));
+        assert_eq!(neighbors.len(), 1);
+        assert_eq!(neighbors[0].to(), index(3, 2));
+        // The walls sensed from (2, 2) are known from its neighbors too
+        assert!(g.edge(index(2, 3), index(2, 2)).is_none());
+        assert!(g.edge(index(2, 3), index(2, 4)).is_some());
+        assert!(g.edge(index(2, 2), index(4, 2)).is_none());
+    }
+    #[test]
+    fn astar_through_unexplored() {
+        let mut base = walled_grid();
+        let cost = astar(&Graph::new(&base.maze, &base), index(0, 0), index(3, 3))
+            .unwrap()
+            .cost();
+        assert_eq!(cost, 6);
+
+        // Once the start is known to open only to the north, the plan goes that way
+        base.maze
+            .sense(CoordXY::new(0, 0).unwrap(), [false, true, true, true]);
+        let route = astar(&Graph::new(&base.maze, &base), index(0, 0), index(3, 3)).unwrap();
+        assert_eq!(route.nodes()[1], index(0, 1));
+    }
+}
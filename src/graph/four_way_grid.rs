@@ -2,52 +2,60 @@
 
 use crate::graph::*;
 
+/// When `ENTRY_EDGE` is true, agent states report the edge through which the agent entered the
+/// cell as their local location instead of `CellLocalLocation::Center`.
 #[derive(Debug)]
-pub struct Graph {
+pub struct FourWayGrid<const ENTRY_EDGE: bool> {
     pub maze: Maze,
 }
-impl Graph {
+pub type Graph = FourWayGrid<false>;
+pub type EntryEdgeGraph = FourWayGrid<true>;
+impl<const ENTRY_EDGE: bool> FourWayGrid<ENTRY_EDGE> {
     fn coord_xy_by_node_index(index: NodeIndex<Self>) -> Result<CoordXY, Error> {
-        let x = (index.value as u8) % WIDTH as u8;
-        let y = (index.value as u8) / WIDTH as u8;
+        let x = (index.value % WIDTH as NodeIndexValue) as u8;
+        let y = (index.value / WIDTH as NodeIndexValue) as u8;
         CoordXY::new(x, y)
     }
     fn vector_xy_by_node_index_pair(from: NodeIndex<Self>, to: NodeIndex<Self>) -> VectorXY {
         Self::coord_xy_by_node_index(to).unwrap() - Self::coord_xy_by_node_index(from).unwrap()
     }
-    fn node_index_diff_by_vector_xy(vector: VectorXY) -> NodeIndexValue {
-        vector.x as NodeIndexValue + vector.y as NodeIndexValue * WIDTH as NodeIndexValue
+    /// Returns the direction from `from` to `to` if they are orthogonally adjacent.
+    #[inline]
+    pub fn direction_between(from: NodeIndex<Self>, to: NodeIndex<Self>) -> Option<Direction> {
+        Self::vector_xy_by_node_index_pair(from, to).try_into().ok()
     }
-    fn node_index_by_coord_xy(coord: CoordXY) -> Result<NodeIndex<Self>, Error> {
+    pub fn node_index_by_coord_xy(coord: CoordXY) -> Result<NodeIndex<Self>, Error> {
         NodeIndex::new(
             coord.x().value() as NodeIndexValue
                 + coord.y().value() as NodeIndexValue * WIDTH as NodeIndexValue,
         )
     }
     fn edge_impl(cell: Cell, direction: Direction, index: NodeIndex<Self>) -> Option<Edge<Self>> {
-        let to = NodeIndex::new(index.value + Self::node_index_diff_by_vector_xy(direction.into()))
-            .unwrap();
-        if !cell.state_by_direction(direction) {
-            return Some(Edge::new(index, to));
+        if cell.state_by_direction(direction) {
+            return None;
         }
-        None
+        // NOTE: stepping on coordinates keeps the edge from wrapping around the grid when the
+        // perimeter has no wall
+        let location = Self::coord_xy_by_node_index(index).ok()?;
+        let to = Self::node_index_by_coord_xy((location + direction.into()).ok()?).ok()?;
+        Some(Edge::new(index, to))
     }
 }
-impl GraphBase for Graph {
+impl<const ENTRY_EDGE: bool> GraphBase for FourWayGrid<ENTRY_EDGE> {
     const MAX_NODE_INDEX: NodeIndexValue = WIDTH as NodeIndexValue * WIDTH as NodeIndexValue - 1;
-    fn cost(from: NodeIndex<Graph>, to: NodeIndex<Graph>) -> Cost {
-        Graph::optimistic_cost(from, to)
+    fn cost(from: NodeIndex<Self>, to: NodeIndex<Self>) -> Cost {
+        Self::optimistic_cost(from, to)
     }
-    fn optimistic_cost(from: NodeIndex<Graph>, to: NodeIndex<Graph>) -> Cost {
+    fn optimistic_cost(from: NodeIndex<Self>, to: NodeIndex<Self>) -> Cost {
         let vector = Self::vector_xy_by_node_index_pair(from, to);
-        vector.x.abs() as Cost + vector.y.abs() as Cost
+        vector.manhattan_len() as Cost
     }
     fn agent_state_by_node_index(
-        index: NodeIndex<Graph>,
-        from_index: Option<NodeIndex<Graph>>,
+        index: NodeIndex<Self>,
+        from_index: Option<NodeIndex<Self>>,
     ) -> AgentState {
-        let x = (index.value as u8) % WIDTH as u8;
-        let y = (index.value as u8) / WIDTH as u8;
+        let x = (index.value % WIDTH as NodeIndexValue) as u8;
+        let y = (index.value / WIDTH as NodeIndexValue) as u8;
         let mut state = AgentState {
             location: CoordXY::new(x, y).unwrap(),
             local_location: CellLocalLocation::Center,
@@ -55,33 +63,46 @@ impl GraphBase for Graph {
         };
         if let Some(from_index) = from_index {
             state.heading_vector = Self::vector_xy_by_node_index_pair(from_index, index);
+            if ENTRY_EDGE {
+                if let Ok(direction) = Direction::try_from(state.heading_vector) {
+                    state.local_location = direction.inverted().into();
+                }
+            }
         }
         state
     }
     fn node_index_by_agent_state(agent_state: AgentState) -> Result<NodeIndex<Self>, Error> {
-        if agent_state.local_location != CellLocalLocation::Center {
+        if !ENTRY_EDGE && agent_state.local_location != CellLocalLocation::Center {
             return Err(Error::InvalidLocation);
         }
         Ok(Self::node_index_by_coord_xy(agent_state.location).unwrap())
     }
+    /// Turning by 90 degrees costs 1 and turning back costs 2, so that routes prefer long
+    /// straight runs.
+    fn turn_cost(from_heading: VectorXY, edge: &Edge<Self>) -> Cost {
+        if from_heading == (VectorXY { x: 0, y: 0 }) {
+            return 0;
+        }
+        match Turn::between(
+            from_heading,
+            Self::vector_xy_by_node_index_pair(edge.from(), edge.to()),
+        ) {
+            Turn::Straight => 0,
+            Turn::Left | Turn::Right => 1,
+            Turn::Back => 2,
+        }
+    }
     fn edge(&self, from: NodeIndex<Self>, to: NodeIndex<Self>) -> Option<Edge<Self>> {
-        let state = Graph::agent_state_by_node_index(from, None);
+        let state = Self::agent_state_by_node_index(from, None);
         let cell = self.maze.cell(state.location);
-        if let Ok(direction) = Self::vector_xy_by_node_index_pair(from, to).try_into() {
-            return Self::edge_impl(cell, direction, from);
-        }
-        None
+        Self::direction_between(from, to)
+            .and_then(|direction| Self::edge_impl(cell, direction, from))
     }
     fn neighbors(&self, from: NodeIndex<Self>) -> Vec<Edge<Self>, MAX_NEIGHBORS> {
-        let state = Graph::agent_state_by_node_index(from, None);
+        let state = Self::agent_state_by_node_index(from, None);
         let cell = self.maze.cell(state.location);
-        let mut vec = Vec::<Edge<Graph>, MAX_NEIGHBORS>::new();
-        for direction in [
-            Direction::North,
-            Direction::East,
-            Direction::South,
-            Direction::West,
-        ] {
+        let mut vec = Vec::<Edge<Self>, MAX_NEIGHBORS>::new();
+        for direction in Direction::iter() {
             if let Some(edge) = Self::edge_impl(cell, direction, from) {
                 vec.push(edge).unwrap();
             }
@@ -90,21 +111,29 @@ impl GraphBase for Graph {
     }
 }
 
+// Maze shared by the tests of the solvers on this grid
+#[cfg(test)]
+pub(crate) const MAZE_STR: &str = "\
+    +   +   +   +   +\n\
+    |                \n\
+    +   +---+---+---+\n\
+    |   |           |\n\
+    +   +   +   +   +\n\
+    |   |           |\n\
+    +---+   +   +   +\n\
+    |               |\n\
+    +---+---+---+---+\n";
+
+// Node at the center of the cell at (`x`, `y`), shared by the tests of the graphs and the solvers
+#[cfg(test)]
+pub(crate) fn index<T: GraphBase>(x: u8, y: u8) -> NodeIndex<T> {
+    node_index_at(CoordXY::new(x, y).unwrap()).unwrap()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const MAZE_STR: &str = "\
-        +   +   +   +   +\n\
-        |                \n\
-        +   +---+---+---+\n\
-        |   |           |\n\
-        +   +   +   +   +\n\
-        |   |           |\n\
-        +---+   +   +   +\n\
-        |               |\n\
-        +---+---+---+---+\n";
-
     #[test]
     fn vector_xy_by_node_index_pair() {
         assert_eq!(
@@ -133,11 +162,48 @@ mod tests {
         );
     }
     #[test]
-    fn node_index_diff_by_vector_xy() {
+    fn direction_between() {
+        let from = NodeIndex::new(WIDTH as NodeIndexValue + 1).unwrap();
         assert_eq!(
-            Graph::node_index_diff_by_vector_xy(VectorXY { x: 2, y: 4 }),
-            WIDTH as NodeIndexValue * 4 + 2
-        )
+            Graph::direction_between(
+                from,
+                NodeIndex::new(2 * WIDTH as NodeIndexValue + 1).unwrap()
+            ),
+            Some(Direction::North)
+        );
+        assert_eq!(
+            Graph::direction_between(from, NodeIndex::new(WIDTH as NodeIndexValue + 2).unwrap()),
+            Some(Direction::East)
+        );
+        assert_eq!(
+            Graph::direction_between(from, NodeIndex::new(1).unwrap()),
+            Some(Direction::South)
+        );
+        assert_eq!(
+            Graph::direction_between(from, NodeIndex::new(WIDTH as NodeIndexValue).unwrap()),
+            Some(Direction::West)
+        );
+    }
+    #[test]
+    fn direction_between_not_adjacent() {
+        let from = NodeIndex::new(WIDTH as NodeIndexValue + 1).unwrap();
+        assert_eq!(Graph::direction_between(from, from), None);
+        assert_eq!(
+            Graph::direction_between(from, NodeIndex::new(WIDTH as NodeIndexValue + 3).unwrap()),
+            None
+        );
+        assert_eq!(
+            Graph::direction_between(from, NodeIndex::new(0).unwrap()),
+            None
+        );
+        // Consecutive indices across the end of a row are not adjacent
+        assert_eq!(
+            Graph::direction_between(
+                NodeIndex::new(WIDTH as NodeIndexValue - 1).unwrap(),
+                NodeIndex::new(WIDTH as NodeIndexValue).unwrap()
+            ),
+            None
+        );
     }
     #[test]
     fn node_index_by_coord_xy() {
@@ -161,6 +227,36 @@ mod tests {
         assert_eq!(edge.to.value, 1);
     }
     #[test]
+    fn edge_impl_perimeter() {
+        // No walls at all, not even on the perimeter
+        let cell = Cell::new();
+        let last = WIDTH as NodeIndexValue - 1;
+        let index: NodeIndex<Graph> = NodeIndex::new(2 * WIDTH as NodeIndexValue + last).unwrap();
+        assert!(Graph::edge_impl(cell, Direction::East, index).is_none());
+        let index: NodeIndex<Graph> = NodeIndex::new(2 * WIDTH as NodeIndexValue).unwrap();
+        assert!(Graph::edge_impl(cell, Direction::West, index).is_none());
+        let index: NodeIndex<Graph> = NodeIndex::new(1).unwrap();
+        assert!(Graph::edge_impl(cell, Direction::South, index).is_none());
+        let index: NodeIndex<Graph> = NodeIndex::new(Graph::MAX_NODE_INDEX).unwrap();
+        assert!(Graph::edge_impl(cell, Direction::North, index).is_none());
+    }
+    #[test]
+    fn neighbors_rightmost_column() {
+        let mut maze = Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(7, 7).unwrap());
+        let coord = CoordXY::new(WIDTH as u8 - 1, 2).unwrap();
+        *maze.mutable_cell(coord) = Cell::new();
+        let g = Graph { maze };
+        let from = Graph::node_index_by_coord_xy(coord).unwrap();
+        let neighbors = g.neighbors(from);
+        assert_eq!(neighbors.len(), 3);
+        // Nothing on the next row past the east edge
+        assert!(neighbors
+            .iter()
+            .all(|edge| edge.to().to_agent_state(None).location.x().value() > 0));
+        let wrapped = NodeIndex::new(from.value() + 1).unwrap();
+        assert!(g.edge(from, wrapped).is_none());
+    }
+    #[test]
     fn cost() {
         let d = Graph::cost(
             NodeIndex::new(1).unwrap(),
@@ -188,6 +284,13 @@ mod tests {
         assert_eq!(state.heading_vector, VectorXY { x: 0, y: 0 });
     }
     #[test]
+    fn agent_state_by_node_index_last_node() {
+        let state =
+            Graph::agent_state_by_node_index(NodeIndex::new(Graph::MAX_NODE_INDEX).unwrap(), None);
+        assert_eq!(state.location.x().value(), Coord1D::MAX);
+        assert_eq!(state.location.y().value(), Coord1D::MAX);
+    }
+    #[test]
     fn agent_state_by_node_index_with_from_index() {
         let state = Graph::agent_state_by_node_index(
             NodeIndex::new(WIDTH as NodeIndexValue + 2).unwrap(),
@@ -199,6 +302,28 @@ mod tests {
         assert_eq!(state.heading_vector, VectorXY { x: 1, y: 0 });
     }
     #[test]
+    fn agent_state_by_node_index_entry_edge() {
+        let state = EntryEdgeGraph::agent_state_by_node_index(
+            NodeIndex::new(WIDTH as NodeIndexValue + 2).unwrap(),
+            Some(NodeIndex::new(WIDTH as NodeIndexValue + 1).unwrap()),
+        );
+        // Entering from the west while heading east
+        assert_eq!(state.local_location, CellLocalLocation::West);
+        assert_eq!(state.heading_vector, VectorXY { x: 1, y: 0 });
+
+        let state = EntryEdgeGraph::agent_state_by_node_index(
+            NodeIndex::new(WIDTH as NodeIndexValue + 2).unwrap(),
+            Some(NodeIndex::new(2).unwrap()),
+        );
+        assert_eq!(state.local_location, CellLocalLocation::South);
+
+        let state = EntryEdgeGraph::agent_state_by_node_index(
+            NodeIndex::new(WIDTH as NodeIndexValue + 2).unwrap(),
+            None,
+        );
+        assert_eq!(state.local_location, CellLocalLocation::Center);
+    }
+    #[test]
     fn node_index_by_agent_state() {
         let node_index = Graph::node_index_by_agent_state(AgentState {
             location: CoordXY::new(2, 3).unwrap(),
@@ -221,7 +346,7 @@ mod tests {
     #[test]
     fn edge() {
         let g = Graph {
-            maze: Maze::load_from_str(MAZE_STR),
+            maze: Maze::load_from_str(MAZE_STR).unwrap(),
         };
         let edge = g.edge(NodeIndex::new(0).unwrap(), NodeIndex::new(1).unwrap());
         assert!(edge.is_some());
@@ -239,7 +364,7 @@ mod tests {
     #[test]
     fn edge_with_invalid_to() {
         let g = Graph {
-            maze: Maze::load_from_str(MAZE_STR),
+            maze: Maze::load_from_str(MAZE_STR).unwrap(),
         };
         let edge = g.edge(NodeIndex::new(0).unwrap(), NodeIndex::new(3).unwrap());
         assert!(edge.is_none());
@@ -247,11 +372,11 @@ mod tests {
     #[test]
     fn neighbors() {
         let g = Graph {
-            maze: Maze::load_from_str(MAZE_STR),
+            maze: Maze::load_from_str(MAZE_STR).unwrap(),
         };
         let n = g.neighbors(NodeIndex::new(0).unwrap());
         assert_eq!(n.len(), 1);
-        let edge = n.get(0).unwrap();
+        let edge = n.first().unwrap();
         assert_eq!(edge.from.value, 0);
         assert_eq!(edge.to.value, 1);
         assert_eq!(edge.cost, 1);
@@ -259,4 +384,14 @@ mod tests {
         let n = g.neighbors(NodeIndex::new(WIDTH as NodeIndexValue + 2).unwrap());
         assert_eq!(n.len(), 4);
     }
+    #[test]
+    fn turn_cost() {
+        let from: NodeIndex<Graph> = NodeIndex::new(WIDTH as NodeIndexValue + 1).unwrap();
+        let east = Edge::new(from, NodeIndex::new(WIDTH as NodeIndexValue + 2).unwrap());
+        assert_eq!(Graph::turn_cost(VectorXY { x: 0, y: 0 }, &east), 0);
+        assert_eq!(Graph::turn_cost(Direction::East.into(), &east), 0);
+        assert_eq!(Graph::turn_cost(Direction::North.into(), &east), 1);
+        assert_eq!(Graph::turn_cost(Direction::South.into(), &east), 1);
+        assert_eq!(Graph::turn_cost(Direction::West.into(), &east), 2);
+    }
 }
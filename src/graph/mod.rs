@@ -2,16 +2,25 @@
 
 use core::marker::PhantomData;
 
+use crate::solver::{NodeSet, MAX_NODES};
 use crate::types::*;
 
+pub mod eight_way_grid;
+pub mod exploration_grid;
 pub mod four_way_grid;
+pub mod overlay;
 
 pub type NodeIndexValue = i16;
 pub type Cost = i32;
 
-const MAX_NEIGHBORS: usize = 8;
+pub const MAX_NEIGHBORS: usize = 8;
+pub const MAX_ROUTE_BYTES: usize = 3 * WIDTH * WIDTH;
+// Marks a node stored as an absolute index instead of a delta from the previous one
+const ROUTE_BYTES_ESCAPE: u8 = i8::MIN as u8;
+/// Number of edges `Maze::route_count` follows before giving up and saturating.
+pub const ROUTE_COUNT_MAX_STEPS: usize = 1 << 16;
 
-#[derive(Debug, Eq)]
+#[derive(Eq)]
 pub struct NodeIndex<T: GraphBase> {
     value: NodeIndexValue,
     graph_type: PhantomData<T>,
@@ -21,7 +30,7 @@ impl<T: GraphBase> NodeIndex<T> {
         if #[cfg(feature = "debug")]{
             #[inline]
             pub fn new(value: NodeIndexValue) -> Result<Self, Error> {
-                if value > T::MAX_NODE_INDEX {
+                if !(0..=T::MAX_NODE_INDEX).contains(&value) {
                     Err(Error::OutOfRange)
                 } else {
                     Ok(Self {
@@ -45,6 +54,17 @@ impl<T: GraphBase> NodeIndex<T> {
     pub fn to_agent_state(&self, from_index: Option<NodeIndex<T>>) -> AgentState {
         T::agent_state_by_node_index(*self, from_index)
     }
+    /// Converts the index into the same node of `U` without going through an agent state.
+    #[inline]
+    pub fn transmute_to<U: GraphBase>(self) -> NodeIndex<U>
+    where
+        T: SharedIndexSpace<U>,
+    {
+        NodeIndex {
+            value: self.value,
+            graph_type: PhantomData,
+        }
+    }
 }
 // NOTE: we need to implement PartialEq and PartialOrd traits manually because T can be incompatible
 impl<T: GraphBase> PartialEq for NodeIndex<T> {
@@ -59,6 +79,12 @@ impl<T: GraphBase> PartialOrd for NodeIndex<T> {
         Some(self.value.cmp(&other.value))
     }
 }
+// NOTE: we need to implement Debug trait manually because T can be non-debuggable
+impl<T: GraphBase> core::fmt::Debug for NodeIndex<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_tuple("NodeIndex").field(&self.value).finish()
+    }
+}
 // NOTE: we need to implement Copy trait manually because T can be non-copiable
 impl<T: GraphBase> Copy for NodeIndex<T> {}
 impl<T: GraphBase> Clone for NodeIndex<T> {
@@ -105,15 +131,442 @@ impl<T: GraphBase> Edge<T> {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+// A straight run of a route
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub start: CoordXY,
+    pub heading_vector: VectorXY,
+    pub length: u8,
+}
+
+#[derive(Debug)]
 pub struct Route<T: GraphBase> {
     // TODO: use MAX_NODE_INDEX (once generic_const_exprs gets stabilized)
     nodes: Vec<NodeIndex<T>, { WIDTH * WIDTH }>,
     cost: Cost,
 }
+// NOTE: we need to implement PartialEq, Eq and Clone traits manually because T can be incompatible
+impl<T: GraphBase> PartialEq for Route<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.nodes == other.nodes && self.cost == other.cost
+    }
+}
+impl<T: GraphBase> Eq for Route<T> {}
+impl<T: GraphBase> Clone for Route<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            nodes: self.nodes.clone(),
+            cost: self.cost,
+        }
+    }
+}
+impl<T: GraphBase> Route<T> {
+    #[inline]
+    pub fn new(nodes: Vec<NodeIndex<T>, { WIDTH * WIDTH }>, cost: Cost) -> Self {
+        Self { nodes, cost }
+    }
+    #[inline]
+    pub fn nodes(&self) -> &[NodeIndex<T>] {
+        &self.nodes
+    }
+    #[inline]
+    pub fn cost(&self) -> Cost {
+        self.cost
+    }
+    /// Returns the number of nodes of the route.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+    /// Iterates over the nodes of the route from the start to the goal.
+    #[inline]
+    pub fn iter(&self) -> core::slice::Iter<'_, NodeIndex<T>> {
+        self.nodes.iter()
+    }
+    /// Iterates over the edges of `graph` between consecutive nodes. The iteration stops early
+    /// at the first pair of nodes which is not connected in `graph`.
+    pub fn edges<'a>(&'a self, graph: &'a T) -> impl Iterator<Item = Edge<T>> + 'a {
+        self.nodes
+            .windows(2)
+            .map_while(move |pair| graph.edge(pair[0], pair[1]))
+    }
+    /// Iterates over the agent states along the route. The first state has no heading and each
+    /// following one is headed along the step which leads to it.
+    pub fn agent_states(&self) -> impl Iterator<Item = AgentState> + '_ {
+        let from_indices = core::iter::once(None).chain(self.nodes.iter().copied().map(Some));
+        self.nodes
+            .iter()
+            .zip(from_indices)
+            .map(|(node, from_index)| node.to_agent_state(from_index))
+    }
+    /// Returns the cost divided by `T::COST_SCALE`, rounded toward zero.
+    #[inline]
+    pub fn cost_unscaled(&self) -> Cost {
+        self.cost / T::COST_SCALE
+    }
+    /// Lists the locations of the nodes of the route.
+    pub fn coords(&self) -> Vec<CoordXY, { WIDTH * WIDTH }> {
+        self.nodes
+            .iter()
+            .map(|node| node.to_agent_state(None).location)
+            .collect()
+    }
+    /// Lists the cells next to the route, through open walls or not, which the route itself does
+    /// not pass through.
+    pub fn adjacent_cells(&self) -> Vec<CoordXY, { WIDTH * WIDTH }> {
+        let coords = self.coords();
+        let mut cells = Vec::<CoordXY, { WIDTH * WIDTH }>::new();
+        for coord in coords.iter() {
+            for adjacent in coord.adjacent() {
+                if !coords.contains(&adjacent) && !cells.contains(&adjacent) {
+                    // NOTE: the cells are unique
+                    cells.push(adjacent).unwrap();
+                }
+            }
+        }
+        cells
+    }
+    /// Lists the steps of the route, as the cell they leave and their direction, which cross a
+    /// wall not checked in `maze` yet. Steps which are not orthogonal are skipped.
+    pub fn unconfirmed_edges(&self, maze: &Maze) -> Vec<(CoordXY, Direction), { WIDTH * WIDTH }> {
+        let mut edges = Vec::<(CoordXY, Direction), { WIDTH * WIDTH }>::new();
+        for pair in self.nodes.windows(2) {
+            let from = pair[0].to_agent_state(None).location;
+            let heading = pair[1].to_agent_state(Some(pair[0])).heading_vector;
+            let Ok(direction) = Direction::try_from(heading) else {
+                continue;
+            };
+            if !maze.edge_checked(from, direction) {
+                // NOTE: there are fewer steps than nodes
+                edges.push((from, direction)).unwrap();
+            }
+        }
+        edges
+    }
+    /// Returns the fewest open sides among the cells of the route, e.g. 2 for a route running
+    /// along corridors only, or 0 for an empty route.
+    pub fn min_clearance(&self, maze: &Maze) -> u8 {
+        self.coords()
+            .iter()
+            .map(|&coord| maze.cell(coord).open_count())
+            .min()
+            .unwrap_or(0)
+    }
+    /// Splits the route into straight runs. Each segment starts at the cell where the previous
+    /// one ends.
+    pub fn segments(&self) -> Vec<Segment, { WIDTH * WIDTH }> {
+        let mut segments = Vec::<Segment, { WIDTH * WIDTH }>::new();
+        for pair in self.nodes.windows(2) {
+            let state = pair[1].to_agent_state(Some(pair[0]));
+            match segments.last_mut() {
+                Some(segment) if segment.heading_vector == state.heading_vector => {
+                    segment.length += 1;
+                }
+                _ => {
+                    // NOTE: there are fewer segments than nodes
+                    segments
+                        .push(Segment {
+                            start: pair[0].to_agent_state(None).location,
+                            heading_vector: state.heading_vector,
+                            length: 1,
+                        })
+                        .unwrap();
+                }
+            }
+        }
+        segments
+    }
+    /// Counts the changes of heading between consecutive edges of the route.
+    pub fn turn_count(&self) -> usize {
+        let mut count = 0;
+        let mut last_heading = None;
+        for pair in self.nodes.windows(2) {
+            let heading = pair[1].to_agent_state(Some(pair[0])).heading_vector;
+            if last_heading.is_some_and(|h| h != heading) {
+                count += 1;
+            }
+            last_heading = Some(heading);
+        }
+        count
+    }
+    /// Converts the route into motion commands for an agent initially heading along
+    /// `start_heading`: a turn whenever the heading changes (including before the first move)
+    /// followed by a forward move over each straight run.
+    pub fn to_commands(&self, start_heading: Direction) -> Vec<Command, MAX_COMMANDS> {
+        let mut commands = Vec::<Command, MAX_COMMANDS>::new();
+        let mut heading: VectorXY = start_heading.into();
+        for segment in self.segments() {
+            // NOTE: there are two commands per segment at most
+            if segment.heading_vector != heading {
+                commands
+                    .push(Command::Turn(Turn::between(
+                        heading,
+                        segment.heading_vector,
+                    )))
+                    .unwrap();
+                heading = segment.heading_vector;
+            }
+            commands.push(Command::Forward(segment.length)).unwrap();
+        }
+        commands
+    }
+    /// Returns the number of commands `to_commands` produces without building them.
+    pub fn command_count(&self, start_heading: Direction) -> usize {
+        let mut count = 0;
+        let mut heading: VectorXY = start_heading.into();
+        let mut forward = false;
+        for pair in self.nodes.windows(2) {
+            let next_heading = pair[1].to_agent_state(Some(pair[0])).heading_vector;
+            if next_heading != heading {
+                count += 1;
+                heading = next_heading;
+                forward = false;
+            }
+            if !forward {
+                count += 1;
+                forward = true;
+            }
+        }
+        count
+    }
+    /// Lists the start, every cell where the route turns, and the goal.
+    pub fn waypoints(&self) -> Vec<CoordXY, { WIDTH * WIDTH }> {
+        let mut waypoints: Vec<CoordXY, { WIDTH * WIDTH }> = self
+            .segments()
+            .iter()
+            .map(|segment| segment.start)
+            .collect();
+        if let Some(last) = self.nodes.last() {
+            // NOTE: there are fewer segments than nodes
+            waypoints.push(last.to_agent_state(None).location).unwrap();
+        }
+        waypoints
+    }
+    /// Lists every cell of the route with the side it is entered from and the side it is left
+    /// toward, e.g. `(South, East)` for a right turn while heading north. The start has no entry
+    /// and the goal no exit, and neither do steps which are not orthogonal.
+    pub fn cell_transits(
+        &self,
+    ) -> Vec<(CoordXY, Option<Direction>, Option<Direction>), { WIDTH * WIDTH }> {
+        let step = |from: NodeIndex<T>, to: NodeIndex<T>| {
+            Direction::try_from(to.to_agent_state(Some(from)).heading_vector).ok()
+        };
+        (0..self.nodes.len())
+            .map(|i| {
+                let entry = i
+                    .checked_sub(1)
+                    .and_then(|j| step(self.nodes[j], self.nodes[i]))
+                    .map(|direction| direction.inverted());
+                let exit = self
+                    .nodes
+                    .get(i + 1)
+                    .and_then(|&next| step(self.nodes[i], next));
+                (self.nodes[i].to_agent_state(None).location, entry, exit)
+            })
+            .collect()
+    }
+    /// Lists the cells where the agent has to be slow enough to turn, i.e. the last cell of each
+    /// straight run before the route turns.
+    pub fn slowdown_points(&self) -> Vec<CoordXY, { WIDTH * WIDTH }> {
+        let mut points = Vec::<CoordXY, { WIDTH * WIDTH }>::new();
+        for pair in self.segments().windows(2) {
+            let heading = pair[0].heading_vector;
+            let back = VectorXY {
+                x: -heading.x,
+                y: -heading.y,
+            };
+            // NOTE: the cell before the corner is on the route, and there are fewer segments
+            // than nodes
+            points.push((pair[1].start + back).unwrap()).unwrap();
+        }
+        points
+    }
+    /// Lists the turns the route makes at junctions (cells with three or more open sides). Turns
+    /// at corners of corridors are omitted since there is only one way to go there.
+    pub fn junction_decisions(&self, maze: &Maze) -> Vec<(CoordXY, Turn), { WIDTH * WIDTH }> {
+        let mut decisions = Vec::<(CoordXY, Turn), { WIDTH * WIDTH }>::new();
+        for pair in self.segments().windows(2) {
+            let location = pair[1].start;
+            if matches!(
+                maze.junction_type(location),
+                JunctionType::ThreeWay | JunctionType::FourWay
+            ) {
+                let turn = Turn::between(pair[0].heading_vector, pair[1].heading_vector);
+                decisions.push((location, turn)).unwrap();
+            }
+        }
+        decisions
+    }
+    /// Returns `true` if this route is no worse than `other` in every criterion and strictly
+    /// better in at least one. The criteria are the total cost, the number of turns (changes of
+    /// heading between consecutive edges) and the length (number of nodes).
+    pub fn dominates(&self, other: &Route<T>) -> bool {
+        let lhs = (self.cost, self.turn_count(), self.nodes.len());
+        let rhs = (other.cost, other.turn_count(), other.nodes.len());
+        lhs.0 <= rhs.0 && lhs.1 <= rhs.1 && lhs.2 <= rhs.2 && lhs != rhs
+    }
+    /// Encodes the route as the first node index (two bytes, little endian) followed by one
+    /// signed byte per node holding the index delta from the previous node. Deltas that do not
+    /// fit in a byte are stored as `ROUTE_BYTES_ESCAPE` followed by the absolute index.
+    pub fn to_bytes(&self) -> Vec<u8, MAX_ROUTE_BYTES> {
+        let mut bytes = Vec::<u8, MAX_ROUTE_BYTES>::new();
+        let mut last: Option<NodeIndexValue> = None;
+        // NOTE: a route has at most WIDTH * WIDTH nodes, each taking up to three bytes
+        for node in self.nodes.iter() {
+            match last.map(|l| i8::try_from(node.value - l)) {
+                Some(Ok(delta)) if delta != i8::MIN => bytes.push(delta as u8).unwrap(),
+                Some(_) => {
+                    bytes.push(ROUTE_BYTES_ESCAPE).unwrap();
+                    bytes.extend_from_slice(&node.value.to_le_bytes()).unwrap();
+                }
+                None => bytes.extend_from_slice(&node.value.to_le_bytes()).unwrap(),
+            }
+            last = Some(node.value);
+        }
+        bytes
+    }
+    /// Decodes a route encoded by `to_bytes`, checking that consecutive nodes are connected in
+    /// `graph`. The cost is recomputed from the edges. Fails with `Error::ParseError` if the
+    /// bytes are truncated or lead to a node index beyond `T::MAX_NODE_INDEX`.
+    pub fn from_bytes(graph: &T, bytes: &[u8]) -> Result<Self, Error> {
+        // NOTE: the bytes may come from anywhere, so the range is checked regardless of `debug`
+        let to_index = |value: NodeIndexValue| -> Result<NodeIndex<T>, Error> {
+            if !(0..=T::MAX_NODE_INDEX).contains(&value) {
+                return Err(Error::ParseError);
+            }
+            NodeIndex::new(value)
+        };
+        let read_index = |bytes: &[u8]| -> Result<NodeIndex<T>, Error> {
+            match bytes {
+                [lo, hi, ..] => to_index(NodeIndexValue::from_le_bytes([*lo, *hi])),
+                _ => Err(Error::ParseError),
+            }
+        };
+        let mut nodes = Vec::<NodeIndex<T>, { WIDTH * WIDTH }>::new();
+        let mut cost = 0;
+        let mut last = read_index(bytes)?;
+        nodes.push(last).map_err(|_| Error::OutOfRange)?;
+        let mut pos = 2;
+        while pos < bytes.len() {
+            let node = if bytes[pos] == ROUTE_BYTES_ESCAPE {
+                pos += 3;
+                read_index(&bytes[pos - 2..])?
+            } else {
+                pos += 1;
+                to_index(last.value + bytes[pos - 1] as i8 as NodeIndexValue)?
+            };
+            cost += graph.edge(last, node).ok_or(Error::InvalidLocation)?.cost();
+            nodes.push(node).map_err(|_| Error::OutOfRange)?;
+            last = node;
+        }
+        Ok(Self { nodes, cost })
+    }
+    /// Encodes the route as its first node followed by the direction of each step. Fails with
+    /// `Error::InvalidLocation` for an empty or disconnected route and with
+    /// `Error::InvalidDirection` if a step is not orthogonal.
+    pub fn to_direction_path(
+        &self,
+        graph: &T,
+    ) -> Result<(NodeIndex<T>, Vec<Direction, { WIDTH * WIDTH }>), Error> {
+        let start = *self.nodes.first().ok_or(Error::InvalidLocation)?;
+        let mut directions = Vec::<Direction, { WIDTH * WIDTH }>::new();
+        for pair in self.nodes.windows(2) {
+            let edge = graph.edge(pair[0], pair[1]).ok_or(Error::InvalidLocation)?;
+            let direction = Direction::try_from(edge.agent_state_at_to().heading_vector)
+                .map_err(|_| Error::InvalidDirection)?;
+            // NOTE: there are fewer steps than nodes
+            directions.push(direction).unwrap();
+        }
+        Ok((start, directions))
+    }
+    /// Decodes a route encoded by `to_direction_path`, checking that every step is passable in
+    /// `graph`. The cost is recomputed from the edges.
+    pub fn from_direction_path(
+        graph: &T,
+        start: NodeIndex<T>,
+        directions: &[Direction],
+    ) -> Result<Self, Error> {
+        let mut nodes = Vec::<NodeIndex<T>, { WIDTH * WIDTH }>::new();
+        let mut cost = 0;
+        let mut last = start;
+        nodes.push(last).map_err(|_| Error::OutOfRange)?;
+        for &direction in directions {
+            let next =
+                node_index_at::<T>((last.to_agent_state(None).location + direction.into())?)?;
+            cost += graph.edge(last, next).ok_or(Error::InvalidLocation)?.cost();
+            nodes.push(next).map_err(|_| Error::OutOfRange)?;
+            last = next;
+        }
+        Ok(Self { nodes, cost })
+    }
+    /// Removes immediate backtracks (A -> B -> A) and recomputes the cost from the edges of
+    /// `graph`. A route which only goes back and forth collapses into its first node.
+    pub fn simplify(&self, graph: &T) -> Result<Self, Error> {
+        let mut nodes = Vec::<NodeIndex<T>, { WIDTH * WIDTH }>::new();
+        for &node in self.nodes.iter() {
+            if nodes.len() >= 2 && nodes[nodes.len() - 2] == node {
+                nodes.pop();
+            } else {
+                // NOTE: `nodes` never grows longer than `self.nodes`
+                nodes.push(node).unwrap();
+            }
+        }
+        let mut cost = 0;
+        for pair in nodes.windows(2) {
+            cost += graph
+                .edge(pair[0], pair[1])
+                .ok_or(Error::InvalidLocation)?
+                .cost();
+        }
+        Ok(Self { nodes, cost })
+    }
+    /// Appends `other`, which has to start where this route ends, summing up the costs. Fails with
+    /// `Error::InvalidLocation` if the routes do not meet and `Error::OutOfRange` if the result
+    /// is too long.
+    pub fn concat(&self, other: &Route<T>) -> Result<Self, Error> {
+        let Some((first, rest)) = other.nodes.split_first() else {
+            return Ok(self.clone());
+        };
+        let mut nodes = self.nodes.clone();
+        if nodes.is_empty() {
+            nodes.push(*first).map_err(|_| Error::OutOfRange)?;
+        } else if nodes.last() != Some(first) {
+            return Err(Error::InvalidLocation);
+        }
+        nodes
+            .extend_from_slice(rest)
+            .map_err(|_| Error::OutOfRange)?;
+        Ok(Self {
+            nodes,
+            cost: self.cost + other.cost,
+        })
+    }
+    /// Returns `true` if the route leads from `start` to `goal` at the minimum possible cost.
+    pub fn is_optimal(&self, graph: &T, start: NodeIndex<T>, goal: NodeIndex<T>) -> bool {
+        if self.nodes.first() != Some(&start) || self.nodes.last() != Some(&goal) {
+            return false;
+        }
+        crate::solver::astar::astar(graph, start, goal)
+            .is_some_and(|optimal| optimal.cost == self.cost)
+    }
+}
+
+/// Marker for graphs whose node indices stand for the same agent states as those of `U`, which
+/// allows `NodeIndex::transmute_to` between them.
+pub trait SharedIndexSpace<U: GraphBase>: GraphBase {}
 
 pub trait GraphBase: Sized {
     const MAX_NODE_INDEX: NodeIndexValue;
+    /// Number of cost units per cell of straight travel. Both `cost` and `optimistic_cost` are
+    /// expressed in these units, so a graph with diagonal moves can use e.g. 10 and keep an
+    /// integer Euclidean heuristic admissible by rounding it down.
+    const COST_SCALE: Cost = 1;
     fn cost(from: NodeIndex<Self>, to: NodeIndex<Self>) -> Cost;
     fn optimistic_cost(from: NodeIndex<Self>, to: NodeIndex<Self>) -> Cost;
     fn agent_state_by_node_index(
@@ -123,11 +576,468 @@ pub trait GraphBase: Sized {
     fn node_index_by_agent_state(agent_state: AgentState) -> Result<NodeIndex<Self>, Error>;
     fn neighbors(&self, from: NodeIndex<Self>) -> Vec<Edge<Self>, MAX_NEIGHBORS>;
     fn edge(&self, from: NodeIndex<Self>, to: NodeIndex<Self>) -> Option<Edge<Self>>;
+    /// Extra cost of taking `edge` when the agent is heading along `from_heading`, which is zero
+    /// if the agent is not heading anywhere. Graphs are free of turn costs by default. Only the
+    /// solvers which track the heading of the agent, such as `astar_from_state` and
+    /// `astar_turn_limited`, charge it; those searching over nodes alone cannot.
+    fn turn_cost(_from_heading: VectorXY, _edge: &Edge<Self>) -> Cost {
+        0
+    }
+}
+
+// Node at the center of the cell at `coord`
+pub(crate) fn node_index_at<T: GraphBase>(coord: CoordXY) -> Result<NodeIndex<T>, Error> {
+    T::node_index_by_agent_state(AgentState {
+        location: coord,
+        local_location: CellLocalLocation::Center,
+        heading_vector: VectorXY { x: 0, y: 0 },
+    })
+}
+
+impl Maze {
+    /// Lists the cells reachable from `from` through the edges of `graph`, in the order they are
+    /// visited.
+    pub fn reachable<T: GraphBase>(
+        &self,
+        graph: &T,
+        from: CoordXY,
+    ) -> Vec<CoordXY, { WIDTH * WIDTH }> {
+        let mut cells = Vec::<CoordXY, { WIDTH * WIDTH }>::new();
+        let Ok(start) = node_index_at::<T>(from) else {
+            return cells;
+        };
+        let mut visited_nodes = NodeSet::new();
+        let mut visited_cells = [false; WIDTH * WIDTH];
+        let mut stack = Vec::<NodeIndex<T>, MAX_NODES>::new();
+        visited_nodes.insert(start.value);
+        stack.push(start).unwrap();
+        while let Some(current) = stack.pop() {
+            let location = current.to_agent_state(None).location;
+            let i = location.x().value() as usize + location.y().value() as usize * WIDTH;
+            if !visited_cells[i] {
+                visited_cells[i] = true;
+                cells.push(location).unwrap();
+            }
+            for edge in graph.neighbors(current) {
+                if !visited_nodes.contains(edge.to.value) {
+                    visited_nodes.insert(edge.to.value);
+                    // NOTE: every node is pushed at most once
+                    stack.push(edge.to).unwrap();
+                }
+            }
+        }
+        cells
+    }
+    /// Lists the cells which can be reached from `start` at a cost of at most `max_steps`, e.g. to
+    /// bound a planning horizon. Edge costs are assumed to be the same in both directions.
+    pub fn reachable_within<T: GraphBase>(
+        &self,
+        graph: &T,
+        start: CoordXY,
+        max_steps: Cost,
+    ) -> Vec<CoordXY, { WIDTH * WIDTH }> {
+        let mut cells = Vec::<CoordXY, { WIDTH * WIDTH }>::new();
+        let Ok(start) = node_index_at::<T>(start) else {
+            return cells;
+        };
+        // NOTE: the costs are symmetric, so the costs to `start` are the costs from it as well
+        let distances = crate::solver::flood::flood_fill(graph, start);
+        for (value, &distance) in distances.iter().enumerate() {
+            if distance > max_steps {
+                continue;
+            }
+            let location = NodeIndex::<T>::new(value as NodeIndexValue)
+                .unwrap()
+                .to_agent_state(None)
+                .location;
+            if !cells.contains(&location) {
+                cells.push(location).unwrap();
+            }
+        }
+        cells
+    }
+    /// Lists the cells whose flood-fill distance to `goal` is exactly `d`.
+    pub fn cells_at_distance<T: GraphBase>(
+        &self,
+        graph: &T,
+        goal: CoordXY,
+        d: Cost,
+    ) -> Vec<CoordXY, { WIDTH * WIDTH }> {
+        let mut cells = Vec::<CoordXY, { WIDTH * WIDTH }>::new();
+        let Ok(goal) = node_index_at::<T>(goal) else {
+            return cells;
+        };
+        let distances = crate::solver::flood::flood_fill(graph, goal);
+        for (value, &distance) in distances.iter().enumerate() {
+            if distance != d {
+                continue;
+            }
+            let location = NodeIndex::<T>::new(value as NodeIndexValue)
+                .unwrap()
+                .to_agent_state(None)
+                .location;
+            if !cells.contains(&location) {
+                cells.push(location).unwrap();
+            }
+        }
+        cells
+    }
+    /// Computes the flood-fill distance from every cell to `goal` for following its gradient.
+    pub fn gradient_field<T: GraphBase>(&self, graph: &T, goal: CoordXY) -> GradientField<'_> {
+        let mut field = GradientField {
+            maze: self,
+            distances: [Cost::MAX; WIDTH * WIDTH],
+        };
+        let Ok(goal) = node_index_at::<T>(goal) else {
+            return field;
+        };
+        let distances = crate::solver::flood::flood_fill(graph, goal);
+        for (value, &distance) in distances.iter().enumerate() {
+            if distance == Cost::MAX {
+                continue;
+            }
+            let location = NodeIndex::<T>::new(value as NodeIndexValue)
+                .unwrap()
+                .to_agent_state(None)
+                .location;
+            // NOTE: a cell may hold several nodes, of which the closest one counts
+            let i = location.x().value() as usize + location.y().value() as usize * WIDTH;
+            field.distances[i] = field.distances[i].min(distance);
+        }
+        field
+    }
+    /// Lists the cells of the grid which cannot be reached from the start.
+    pub fn unreachable_cells<T: GraphBase>(&self, graph: &T) -> Vec<CoordXY, { WIDTH * WIDTH }> {
+        let mut reachable = [false; WIDTH * WIDTH];
+        for location in self.reachable(graph, self.start) {
+            reachable[location.x().value() as usize + location.y().value() as usize * WIDTH] = true;
+        }
+        let mut cells = Vec::<CoordXY, { WIDTH * WIDTH }>::new();
+        for y in 0..WIDTH as u8 {
+            for x in 0..WIDTH as u8 {
+                if !reachable[x as usize + y as usize * WIDTH] {
+                    cells.push(CoordXY::new(x, y).unwrap()).unwrap();
+                }
+            }
+        }
+        cells
+    }
+    /// Closes all walls of every cell that cannot be reached from the start through `graph`, which
+    /// is expected to reflect the walls of this maze. Returns the number of cells which were not
+    /// closed already.
+    pub fn seal_unreachable<T: GraphBase>(&mut self, graph: &T) -> usize {
+        let mut cells = self.unreachable_cells(graph);
+        // NOTE: closing a cell also closes the walls it shares with its neighbors
+        cells.retain(|&coord| self.openness_mask(coord) != 0);
+        for &coord in cells.iter() {
+            use Direction::*;
+            for direction in [North, East, South, West] {
+                self.set_cell_state(coord, direction, true);
+            }
+        }
+        cells.len()
+    }
+    /// Number of edges leaving the node at the center of the cell at `coord`.
+    pub fn degree<T: GraphBase>(&self, graph: &T, coord: CoordXY) -> usize {
+        node_index_at::<T>(coord).map_or(0, |node| graph.neighbors(node).len())
+    }
+    /// Counts the junctions (cells of degree 3 or more) and averages the degree over the cells
+    /// reachable from the start.
+    pub fn branching_stats<T: GraphBase>(&self, graph: &T) -> BranchingStats {
+        let mut stats = BranchingStats {
+            junctions: 0,
+            average_degree_x100: 0,
+        };
+        let cells = self.reachable(graph, self.start);
+        let mut total = 0;
+        for &coord in cells.iter() {
+            let degree = self.degree(graph, coord);
+            if degree >= 3 {
+                stats.junctions += 1;
+            }
+            total += degree;
+        }
+        if !cells.is_empty() {
+            stats.average_degree_x100 = 100 * total / cells.len();
+        }
+        stats
+    }
+    /// Finds the pair of cells reachable from the start whose shortest route is the longest, along
+    /// with its cost. Every cell is flooded in turn, so loops in the maze are handled correctly.
+    pub fn diameter_endpoints<T: GraphBase>(&self, graph: &T) -> Option<(CoordXY, CoordXY, Cost)> {
+        let mut reachable = [false; WIDTH * WIDTH];
+        for location in self.reachable(graph, self.start) {
+            reachable[location.x().value() as usize + location.y().value() as usize * WIDTH] = true;
+        }
+        let mut best: Option<(CoordXY, CoordXY, Cost)> = None;
+        for y in 0..WIDTH as u8 {
+            for x in 0..WIDTH as u8 {
+                if !reachable[x as usize + y as usize * WIDTH] {
+                    continue;
+                }
+                let from = CoordXY::new(x, y).unwrap();
+                let distances =
+                    crate::solver::flood::flood_fill(graph, node_index_at::<T>(from).ok()?);
+                for (value, &distance) in distances.iter().enumerate() {
+                    if distance == Cost::MAX || best.is_some_and(|(_, _, d)| distance <= d) {
+                        continue;
+                    }
+                    let to = NodeIndex::<T>::new(value as NodeIndexValue)
+                        .unwrap()
+                        .to_agent_state(None)
+                        .location;
+                    best = Some((from, to, distance));
+                }
+            }
+        }
+        best
+    }
+    /// Counts the distinct simple routes from the start to the goal. A perfect maze has exactly
+    /// one, and every loop on the way adds more. Nodes which cannot reach the goal are never
+    /// entered, and the count saturates at `u32::MAX` once the search has followed
+    /// `ROUTE_COUNT_MAX_STEPS` edges, since the number of routes explodes in open areas.
+    pub fn route_count<T: GraphBase>(&self, graph: &T) -> u32 {
+        let (Ok(start), Ok(goal)) = (
+            node_index_at::<T>(self.start),
+            node_index_at::<T>(self.goal),
+        ) else {
+            return 0;
+        };
+        let distances = crate::solver::flood::flood_fill(graph, goal);
+        if distances[start.value as usize] == Cost::MAX {
+            return 0;
+        }
+        if start == goal {
+            return 1;
+        }
+        let mut count: u32 = 0;
+        let mut steps = 0;
+        let mut on_route = [false; MAX_NODES];
+        // NOTE: each entry holds a node of the current route and the next neighbor to try
+        let mut stack = Vec::<(NodeIndex<T>, usize), MAX_NODES>::new();
+        on_route[start.value as usize] = true;
+        stack.push((start, 0)).unwrap();
+        while let Some((current, k)) = stack.last_mut() {
+            let Some(to) = graph.neighbors(*current).get(*k).map(|edge| edge.to) else {
+                on_route[current.value as usize] = false;
+                stack.pop();
+                continue;
+            };
+            *k += 1;
+            let next = to.value as usize;
+            if on_route[next] || distances[next] == Cost::MAX {
+                continue;
+            }
+            steps += 1;
+            if steps > ROUTE_COUNT_MAX_STEPS {
+                return u32::MAX;
+            }
+            if to == goal {
+                count = count.saturating_add(1);
+                continue;
+            }
+            on_route[next] = true;
+            // NOTE: a node is on the route at most once
+            stack.push((to, 0)).unwrap();
+        }
+        count
+    }
+    /// Finds a minimum set of walls which disconnects the start from the goal, by computing the
+    /// maximum flow between them with a unit capacity on every open edge. The cut closest to the
+    /// start is returned. Edges which do not correspond to a wall (e.g. diagonal ones) are left out.
+    pub fn min_cut_to_goal<T: GraphBase>(
+        &self,
+        graph: &T,
+    ) -> Vec<(CoordXY, Direction), { WIDTH * WIDTH }> {
+        let mut cut = Vec::<(CoordXY, Direction), { WIDTH * WIDTH }>::new();
+        let (Ok(start), Ok(goal)) = (
+            node_index_at::<T>(self.start),
+            node_index_at::<T>(self.goal),
+        ) else {
+            return cut;
+        };
+        if start == goal {
+            return cut;
+        }
+        // NOTE: `flows[i][k]` is the flow along the k-th edge of `graph.neighbors(i)`
+        let mut flows = [[0i8; MAX_NEIGHBORS]; MAX_NODES];
+        loop {
+            // Breadth-first search for an augmenting path in the residual graph
+            let mut parents = [(-1 as NodeIndexValue, 0usize); MAX_NODES];
+            let mut visited = NodeSet::new();
+            let mut queue = heapless::Deque::<NodeIndex<T>, MAX_NODES>::new();
+            visited.insert(start.value);
+            queue.push_back(start).unwrap();
+            while let Some(current) = queue.pop_front() {
+                for (k, edge) in graph.neighbors(current).iter().enumerate() {
+                    if visited.contains(edge.to.value) || flows[current.value as usize][k] >= 1 {
+                        continue;
+                    }
+                    visited.insert(edge.to.value);
+                    parents[edge.to.value as usize] = (current.value, k);
+                    // NOTE: every node is queued at most once
+                    queue.push_back(edge.to).unwrap();
+                }
+            }
+            if !visited.contains(goal.value) {
+                // The nodes visited last form the start side of the cut
+                for value in 0..=T::MAX_NODE_INDEX {
+                    if !visited.contains(value) {
+                        continue;
+                    }
+                    let from = NodeIndex::<T>::new(value).unwrap();
+                    let location = from.to_agent_state(None).location;
+                    for edge in graph.neighbors(from) {
+                        if visited.contains(edge.to.value) {
+                            continue;
+                        }
+                        let vector = edge.to.to_agent_state(None).location - location;
+                        if let Ok(direction) = Direction::try_from(vector) {
+                            // NOTE: each wall is listed once, from the start side
+                            cut.push((location, direction)).unwrap();
+                        }
+                    }
+                }
+                return cut;
+            }
+            let mut current = goal;
+            while current != start {
+                let (parent, k) = parents[current.value as usize];
+                let parent = NodeIndex::<T>::new(parent).unwrap();
+                flows[parent.value as usize][k] += 1;
+                if let Some(back) = graph.neighbors(current).iter().position(|e| e.to == parent) {
+                    flows[current.value as usize][back] -= 1;
+                }
+                current = parent;
+            }
+        }
+    }
+}
+
+/// Distances from every cell of a maze to a goal, see `Maze::gradient_field`.
+pub struct GradientField<'a> {
+    maze: &'a Maze,
+    distances: [Cost; WIDTH * WIDTH],
+}
+impl GradientField<'_> {
+    /// Returns the distance from `coord` to the goal, or `Cost::MAX` if it cannot be reached.
+    #[inline]
+    pub fn distance(&self, coord: CoordXY) -> Cost {
+        self.distances[coord.x().value() as usize + coord.y().value() as usize * WIDTH]
+    }
+    /// Returns the direction of the open neighbor of `coord` closest to the goal, or `None` at
+    /// the goal itself and wherever the goal cannot be reached. Ties are broken in the order of
+    /// north, east, south and west.
+    pub fn descent_direction(&self, coord: CoordXY) -> Option<Direction> {
+        use Direction::*;
+        let mut best = (self.distance(coord), None);
+        for direction in [North, East, South, West] {
+            if let Some(next) = self.maze.step(coord, direction) {
+                if self.distance(next) < best.0 {
+                    best = (self.distance(next), Some(direction));
+                }
+            }
+        }
+        best.1
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BranchingStats {
+    pub junctions: usize,
+    // NOTE: the average is scaled by 100 and rounded down to stay in integers
+    pub average_degree_x100: usize,
+}
+
+// Maze with a start and a goal shared by the tests of the graphs and the solvers
+#[cfg(test)]
+pub(crate) const GOAL_MAZE_STR: &str = "\
+    +---+---+---+---+\n\
+    |       |     G |\n\
+    +   +---+   +   +\n\
+    |           |   |\n\
+    +---+   +---+   +\n\
+    |   |           |\n\
+    +   +   +---+---+\n\
+    | S             |\n\
+    +---+---+---+---+\n";
+
+// Costs of a `ScaledGrid`, in terms of the nodes and the costs of the underlying four-way grid
+#[cfg(test)]
+pub(crate) trait CostScaling {
+    const COST_SCALE: Cost = 1;
+    fn cost(
+        _from: NodeIndex<four_way_grid::Graph>,
+        _to: NodeIndex<four_way_grid::Graph>,
+        base: Cost,
+    ) -> Cost {
+        base * Self::COST_SCALE
+    }
+    fn optimistic_cost(
+        from: NodeIndex<four_way_grid::Graph>,
+        to: NodeIndex<four_way_grid::Graph>,
+    ) -> Cost {
+        four_way_grid::Graph::optimistic_cost(from, to) * Self::COST_SCALE
+    }
+}
+
+// Four-way grid with the same nodes and edges but the costs given by `C`
+#[cfg(test)]
+pub(crate) struct ScaledGrid<C>(four_way_grid::Graph, PhantomData<C>);
+#[cfg(test)]
+impl<C: CostScaling> ScaledGrid<C> {
+    pub(crate) fn new(graph: four_way_grid::Graph) -> Self {
+        Self(graph, PhantomData)
+    }
+    fn to_base(index: NodeIndex<Self>) -> NodeIndex<four_way_grid::Graph> {
+        index.transmute_to()
+    }
+    fn from_base(index: NodeIndex<four_way_grid::Graph>) -> NodeIndex<Self> {
+        index.transmute_to()
+    }
+}
+#[cfg(test)]
+impl<C: CostScaling> SharedIndexSpace<four_way_grid::Graph> for ScaledGrid<C> {}
+#[cfg(test)]
+impl<C: CostScaling> SharedIndexSpace<ScaledGrid<C>> for four_way_grid::Graph {}
+#[cfg(test)]
+impl<C: CostScaling> GraphBase for ScaledGrid<C> {
+    const MAX_NODE_INDEX: NodeIndexValue = four_way_grid::Graph::MAX_NODE_INDEX;
+    const COST_SCALE: Cost = C::COST_SCALE;
+    fn cost(from: NodeIndex<Self>, to: NodeIndex<Self>) -> Cost {
+        let (from, to) = (Self::to_base(from), Self::to_base(to));
+        C::cost(from, to, four_way_grid::Graph::cost(from, to))
+    }
+    fn optimistic_cost(from: NodeIndex<Self>, to: NodeIndex<Self>) -> Cost {
+        C::optimistic_cost(Self::to_base(from), Self::to_base(to))
+    }
+    fn agent_state_by_node_index(
+        index: NodeIndex<Self>,
+        from_index: Option<NodeIndex<Self>>,
+    ) -> AgentState {
+        Self::to_base(index).to_agent_state(from_index.map(Self::to_base))
+    }
+    fn node_index_by_agent_state(agent_state: AgentState) -> Result<NodeIndex<Self>, Error> {
+        four_way_grid::Graph::node_index_by_agent_state(agent_state).map(Self::from_base)
+    }
+    fn neighbors(&self, from: NodeIndex<Self>) -> Vec<Edge<Self>, MAX_NEIGHBORS> {
+        self.0
+            .neighbors(Self::to_base(from))
+            .iter()
+            .map(|e| Edge::new(from, Self::from_base(e.to)))
+            .collect()
+    }
+    fn edge(&self, from: NodeIndex<Self>, to: NodeIndex<Self>) -> Option<Edge<Self>> {
+        self.0
+            .edge(Self::to_base(from), Self::to_base(to))
+            .map(|_| Edge::new(from, to))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::solver::astar::astar;
 
     pub struct DummyGraph {}
     impl GraphBase for DummyGraph {
@@ -170,6 +1080,10 @@ mod tests {
             NodeIndex::<DummyGraph>::new(11).err(),
             Some(Error::OutOfRange)
         );
+        assert_eq!(
+            NodeIndex::<DummyGraph>::new(-1).err(),
+            Some(Error::OutOfRange)
+        );
     }
     #[test]
     fn node_index_to_agent_state() {
@@ -220,4 +1134,778 @@ mod tests {
             }
         );
     }
+
+    fn open_grid() -> four_way_grid::Graph {
+        four_way_grid::Graph {
+            maze: Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(7, 7).unwrap()),
+        }
+    }
+    fn grid_route(coords: &[(u8, u8)]) -> Route<four_way_grid::Graph> {
+        let mut nodes = Vec::new();
+        for &(x, y) in coords {
+            nodes
+                .push(
+                    NodeIndex::new(
+                        x as NodeIndexValue + y as NodeIndexValue * WIDTH as NodeIndexValue,
+                    )
+                    .unwrap(),
+                )
+                .unwrap();
+        }
+        Route {
+            cost: nodes.len() as Cost - 1,
+            nodes,
+        }
+    }
+
+    #[test]
+    fn route_to_bytes_from_bytes() {
+        let g = open_grid();
+        let route = grid_route(&[(0, 0), (1, 0), (1, 1), (0, 1), (0, 2)]);
+        let bytes = route.to_bytes();
+        // Two bytes for the first node and one byte per adjacent move
+        assert_eq!(bytes.len(), 2 + 4);
+        assert_eq!(Route::from_bytes(&g, &bytes), Ok(route));
+    }
+    #[test]
+    fn route_to_bytes_escaped() {
+        let last = WIDTH as u8 - 1;
+        let route = grid_route(&[(0, 0), (last, last)]);
+        let bytes = route.to_bytes();
+        let delta = route.nodes()[1].value();
+        // NOTE: on small mazes every delta fits in a byte
+        if let Ok(delta) = i8::try_from(delta) {
+            assert_eq!(bytes[..], [0, 0, delta as u8]);
+        } else {
+            let [lo, hi] = delta.to_le_bytes();
+            assert_eq!(bytes[..], [0, 0, ROUTE_BYTES_ESCAPE, lo, hi]);
+        }
+        // (0, 0) and the opposite corner are not adjacent
+        assert_eq!(
+            Route::from_bytes(&open_grid(), &bytes),
+            Err(Error::InvalidLocation)
+        );
+    }
+    #[test]
+    fn route_from_bytes_invalid() {
+        let g = open_grid();
+        assert_eq!(Route::from_bytes(&g, &[0]), Err(Error::ParseError));
+        assert_eq!(
+            Route::from_bytes(&g, &[0, 0, ROUTE_BYTES_ESCAPE, 1]),
+            Err(Error::ParseError)
+        );
+        let mut g = open_grid();
+        g.maze
+            .set_cell_state(CoordXY::new(0, 0).unwrap(), Direction::East, true);
+        let route = grid_route(&[(0, 0), (1, 0)]);
+        assert_eq!(
+            Route::from_bytes(&g, &route.to_bytes()),
+            Err(Error::InvalidLocation)
+        );
+        // A delta leading off the grid
+        assert_eq!(
+            Route::from_bytes(&g, &[0, 0, (-1i8) as u8]),
+            Err(Error::ParseError)
+        );
+        // Indices beyond the grid, alone or as the target of an escape
+        let beyond = (four_way_grid::Graph::MAX_NODE_INDEX + 1).to_le_bytes();
+        assert_eq!(Route::from_bytes(&g, &beyond), Err(Error::ParseError));
+        assert_eq!(
+            Route::from_bytes(&g, &[0, 0, ROUTE_BYTES_ESCAPE, beyond[0], beyond[1]]),
+            Err(Error::ParseError)
+        );
+        let last = four_way_grid::Graph::MAX_NODE_INDEX.to_le_bytes();
+        assert_eq!(
+            Route::from_bytes(&g, &[last[0], last[1], 1]),
+            Err(Error::ParseError)
+        );
+    }
+    #[test]
+    fn route_simplify() {
+        let g = open_grid();
+        let mut route = grid_route(&[
+            (0, 0),
+            (1, 0),
+            (2, 0),
+            (1, 0),
+            (1, 1),
+            (1, 2),
+            (1, 1),
+            (2, 1),
+        ]);
+        route.cost = 7;
+        assert_eq!(
+            route.simplify(&g),
+            Ok(grid_route(&[(0, 0), (1, 0), (1, 1), (2, 1)]))
+        );
+        // Nested backtracks unwind completely
+        let route = grid_route(&[(0, 0), (1, 0), (2, 0), (1, 0), (0, 0), (0, 1)]);
+        assert_eq!(route.simplify(&g), Ok(grid_route(&[(0, 0), (0, 1)])));
+    }
+    #[test]
+    fn route_simplify_back_and_forth() {
+        let g = open_grid();
+        let route = grid_route(&[(0, 0), (1, 0), (0, 0), (1, 0), (0, 0)]);
+        let simplified = route.simplify(&g).unwrap();
+        assert_eq!(simplified.nodes(), grid_route(&[(0, 0)]).nodes());
+        assert_eq!(simplified.cost(), 0);
+    }
+    #[test]
+    fn route_simplify_invalid_edge() {
+        let mut g = open_grid();
+        g.maze
+            .set_cell_state(CoordXY::new(1, 0).unwrap(), Direction::North, true);
+        let route = grid_route(&[(0, 0), (1, 0), (1, 1)]);
+        assert_eq!(route.simplify(&g), Err(Error::InvalidLocation));
+    }
+    #[test]
+    fn route_concat() {
+        let first = grid_route(&[(0, 0), (1, 0), (1, 1)]);
+        let second = grid_route(&[(1, 1), (1, 2)]);
+        assert_eq!(
+            first.concat(&second),
+            Ok(grid_route(&[(0, 0), (1, 0), (1, 1), (1, 2)]))
+        );
+        assert_eq!(second.concat(&first), Err(Error::InvalidLocation));
+        let empty = Route::new(Vec::new(), 0);
+        assert_eq!(empty.concat(&first), Ok(first.clone()));
+        assert_eq!(first.concat(&empty), Ok(first));
+    }
+    #[test]
+    fn route_is_optimal() {
+        let g = open_grid();
+        let (start, goal) = (
+            grid_route(&[(0, 0)]).nodes[0],
+            grid_route(&[(2, 1)]).nodes[0],
+        );
+        let route = crate::solver::astar::astar(&g, start, goal).unwrap();
+        assert!(route.is_optimal(&g, start, goal));
+        let detour = grid_route(&[(0, 0), (0, 1), (0, 2), (1, 2), (2, 2), (2, 1)]);
+        assert!(!detour.is_optimal(&g, start, goal));
+        // Optimal, but between different endpoints
+        assert!(!route.is_optimal(&g, start, start));
+    }
+    #[test]
+    fn route_adjacent_cells() {
+        let route = grid_route(&[(0, 0), (1, 0), (1, 1)]);
+        assert_eq!(
+            route.coords(),
+            [
+                CoordXY::new(0, 0).unwrap(),
+                CoordXY::new(1, 0).unwrap(),
+                CoordXY::new(1, 1).unwrap(),
+            ]
+        );
+        assert_eq!(
+            route.adjacent_cells(),
+            [
+                CoordXY::new(0, 1).unwrap(),
+                CoordXY::new(2, 0).unwrap(),
+                CoordXY::new(1, 2).unwrap(),
+                CoordXY::new(2, 1).unwrap(),
+            ]
+        );
+    }
+    #[test]
+    fn route_segments() {
+        let route = grid_route(&[(0, 0), (1, 0), (2, 0), (2, 1), (2, 2), (2, 3), (1, 3)]);
+        assert_eq!(
+            route.segments(),
+            [
+                Segment {
+                    start: CoordXY::new(0, 0).unwrap(),
+                    heading_vector: VectorXY { x: 1, y: 0 },
+                    length: 2
+                },
+                Segment {
+                    start: CoordXY::new(2, 0).unwrap(),
+                    heading_vector: VectorXY { x: 0, y: 1 },
+                    length: 3
+                },
+                Segment {
+                    start: CoordXY::new(2, 3).unwrap(),
+                    heading_vector: VectorXY { x: -1, y: 0 },
+                    length: 1
+                },
+            ]
+        );
+        assert!(grid_route(&[(0, 0)]).segments().is_empty());
+    }
+    #[test]
+    fn route_waypoints() {
+        let route = grid_route(&[(0, 0), (1, 0), (2, 0), (2, 1), (2, 2)]);
+        assert_eq!(
+            route.waypoints(),
+            [
+                CoordXY::new(0, 0).unwrap(),
+                CoordXY::new(2, 0).unwrap(),
+                CoordXY::new(2, 2).unwrap(),
+            ]
+        );
+        assert_eq!(
+            grid_route(&[(3, 3)]).waypoints(),
+            [CoordXY::new(3, 3).unwrap()]
+        );
+    }
+    #[test]
+    fn route_cell_transits() {
+        use Direction::*;
+        let route = grid_route(&[(0, 0), (1, 0), (1, 1)]);
+        let transits = route.cell_transits();
+        assert_eq!(
+            transits,
+            [
+                (CoordXY::new(0, 0).unwrap(), None, Some(East)),
+                (CoordXY::new(1, 0).unwrap(), Some(West), Some(North)),
+                (CoordXY::new(1, 1).unwrap(), Some(South), None),
+            ]
+        );
+        // The corner is entered and left on perpendicular sides
+        let (_, entry, exit) = transits[1];
+        let (entry, exit): (VectorXY, VectorXY) = (entry.unwrap().into(), exit.unwrap().into());
+        assert_eq!(entry.x * exit.x + entry.y * exit.y, 0);
+
+        assert_eq!(
+            grid_route(&[(2, 2)]).cell_transits(),
+            [(CoordXY::new(2, 2).unwrap(), None, None)]
+        );
+    }
+    #[test]
+    fn route_slowdown_points() {
+        let route = grid_route(&[(0, 0), (1, 0), (2, 0), (2, 1), (2, 2)]);
+        assert_eq!(route.slowdown_points(), [CoordXY::new(1, 0).unwrap()]);
+        let route = grid_route(&[(0, 0), (0, 1), (1, 1), (1, 2)]);
+        assert_eq!(
+            route.slowdown_points(),
+            [CoordXY::new(0, 0).unwrap(), CoordXY::new(0, 1).unwrap()]
+        );
+        assert!(grid_route(&[(0, 0), (1, 0), (2, 0)])
+            .slowdown_points()
+            .is_empty());
+    }
+    #[test]
+    fn route_junction_decisions() {
+        // A corridor from (0, 0) going east, bending north at (3, 0) and reaching a junction at
+        // (3, 2) where the route turns west
+        let maze = Maze::load_from_str(
+            "\
+            +---+---+---+---+\n\
+            |   |           |\n\
+            +   +---+---+   +\n\
+            |               |\n\
+            +---+---+---+   +\n\
+            |           |   |\n\
+            +---+---+---+   +\n\
+            |               |\n\
+            +---+---+---+---+\n",
+        )
+        .unwrap();
+        let route = grid_route(&[
+            (0, 0),
+            (1, 0),
+            (2, 0),
+            (3, 0),
+            (3, 1),
+            (3, 2),
+            (2, 2),
+            (1, 2),
+            (0, 2),
+            (0, 3),
+        ]);
+        assert_eq!(
+            route.junction_decisions(&maze),
+            [(CoordXY::new(3, 2).unwrap(), Turn::Left)]
+        );
+    }
+    #[test]
+    fn maze_unreachable_cells() {
+        let mut graph = open_grid();
+        let island = CoordXY::new(3, 4).unwrap();
+        for direction in [
+            Direction::North,
+            Direction::East,
+            Direction::South,
+            Direction::West,
+        ] {
+            graph.maze.set_cell_state(island, direction, true);
+        }
+        let unreachable = graph.maze.unreachable_cells(&graph);
+        assert_eq!(unreachable, [island]);
+        assert_eq!(
+            graph.maze.reachable(&graph, graph.maze.start).len(),
+            WIDTH * WIDTH - 1
+        );
+        assert!(open_grid().maze.unreachable_cells(&open_grid()).is_empty());
+    }
+    // Four-way grid in tenths of a cell with a Euclidean heuristic
+    struct Tenths;
+    impl CostScaling for Tenths {
+        const COST_SCALE: Cost = 10;
+        fn optimistic_cost(
+            from: NodeIndex<four_way_grid::Graph>,
+            to: NodeIndex<four_way_grid::Graph>,
+        ) -> Cost {
+            let v = to.to_agent_state(None).location - from.to_agent_state(None).location;
+            let squared = (v.x as Cost * v.x as Cost + v.y as Cost * v.y as Cost)
+                * Self::COST_SCALE
+                * Self::COST_SCALE;
+            let mut root = 0;
+            while (root + 1) * (root + 1) <= squared {
+                root += 1;
+            }
+            root
+        }
+    }
+    type TenthsGrid = ScaledGrid<Tenths>;
+
+    #[test]
+    fn scaled_cost_admissible() {
+        let graph = TenthsGrid::new(open_grid());
+        let start = NodeIndex::new(0).unwrap();
+        for y in 0..8 {
+            for x in 0..8 {
+                let goal = NodeIndex::new(x + y * WIDTH as NodeIndexValue).unwrap();
+                let route = astar(&graph, start, goal).unwrap();
+                assert!(TenthsGrid::optimistic_cost(start, goal) <= route.cost());
+                assert_eq!(route.cost_unscaled(), (x + y) as Cost);
+            }
+        }
+        assert_eq!(
+            TenthsGrid::optimistic_cost(
+                start,
+                NodeIndex::new(1 + WIDTH as NodeIndexValue).unwrap()
+            ),
+            14
+        );
+    }
+    #[test]
+    fn route_cost_unscaled() {
+        let route = Route::<TenthsGrid>::new(Vec::new(), 37);
+        assert_eq!(route.cost_unscaled(), 3);
+        assert_eq!(grid_route(&[(0, 0), (1, 0), (1, 1)]).cost_unscaled(), 2);
+    }
+    #[test]
+    fn maze_cells_at_distance() {
+        let mut graph = open_grid();
+        let goal = CoordXY::new(3, 3).unwrap();
+        graph.maze.set_cell_state(goal, Direction::North, true);
+        assert_eq!(graph.maze.cells_at_distance(&graph, goal, 0), [goal]);
+        assert_eq!(
+            graph.maze.cells_at_distance(&graph, goal, 1),
+            [
+                CoordXY::new(3, 2).unwrap(),
+                CoordXY::new(2, 3).unwrap(),
+                CoordXY::new(4, 3).unwrap(),
+            ]
+        );
+    }
+    #[test]
+    fn maze_reachable_within() {
+        let mut graph = open_grid();
+        let start = CoordXY::new(3, 3).unwrap();
+        graph.maze.set_cell_state(start, Direction::North, true);
+        assert_eq!(graph.maze.reachable_within(&graph, start, 0), [start]);
+        assert_eq!(
+            graph.maze.reachable_within(&graph, start, 1),
+            [
+                CoordXY::new(3, 2).unwrap(),
+                CoordXY::new(2, 3).unwrap(),
+                start,
+                CoordXY::new(4, 3).unwrap(),
+            ]
+        );
+        // The cell behind the wall is two steps further around it
+        let behind = CoordXY::new(3, 4).unwrap();
+        assert!(!graph
+            .maze
+            .reachable_within(&graph, start, 2)
+            .contains(&behind));
+        assert!(graph
+            .maze
+            .reachable_within(&graph, start, 3)
+            .contains(&behind));
+        // A diamond of 13 cells but for the two north of the wall
+        assert_eq!(graph.maze.reachable_within(&graph, start, 2).len(), 11);
+    }
+    #[test]
+    fn maze_gradient_field_descent() {
+        let graph = four_way_grid::Graph {
+            maze: Maze::load_from_str(GOAL_MAZE_STR).unwrap(),
+        };
+        let maze = &graph.maze;
+        let field = maze.gradient_field(&graph, maze.goal);
+        assert_eq!(field.distance(maze.goal), 0);
+        assert_eq!(field.descent_direction(maze.goal), None);
+        let mut coord = maze.start;
+        let mut steps = 0;
+        while let Some(direction) = field.descent_direction(coord) {
+            coord = maze.step(coord, direction).unwrap();
+            steps += 1;
+        }
+        assert_eq!(coord, maze.goal);
+        assert_eq!(steps, field.distance(maze.start));
+
+        // Outside of the 4x4 maze
+        let outside = CoordXY::new(5, 5).unwrap();
+        assert_eq!(field.distance(outside), Cost::MAX);
+        assert_eq!(field.descent_direction(outside), None);
+    }
+    #[test]
+    fn maze_seal_unreachable() {
+        // (1, 3) and (2, 3) form a pocket cut off from the rest
+        let maze_str = "\
+            +---+---+---+---+\n\
+            |   |       | G |\n\
+            +   +---+---+   +\n\
+            |               |\n\
+            +   +---+   +   +\n\
+            |   |   |       |\n\
+            +   +---+   +   +\n\
+            | S             |\n\
+            +---+---+---+---+\n";
+        let graph = four_way_grid::Graph {
+            maze: Maze::load_from_str(maze_str).unwrap(),
+        };
+        let mut maze = Maze::load_from_str(maze_str).unwrap();
+        // The pocket and all open cells outside of the 4x4 maze
+        let unreachable = graph.maze.unreachable_cells(&graph);
+        let open = |maze: &Maze, coords: &[CoordXY]| {
+            coords
+                .iter()
+                .filter(|&&coord| maze.openness_mask(coord) != 0)
+                .count()
+        };
+        let expected = open(&maze, &unreachable);
+        assert!(expected >= 2);
+        assert_eq!(maze.seal_unreachable(&graph), expected);
+        assert_eq!(
+            maze.junction_type(CoordXY::new(1, 3).unwrap()),
+            JunctionType::Closed
+        );
+        assert_eq!(
+            maze.junction_type(CoordXY::new(1, 1).unwrap()),
+            JunctionType::Closed
+        );
+
+        let graph = four_way_grid::Graph { maze };
+        let all = graph.maze.unreachable_cells(&graph);
+        assert_eq!(open(&graph.maze, &all), 0);
+        let mut cells = Vec::<CoordXY, { WIDTH * WIDTH }>::new();
+        for y in 0..WIDTH as u8 {
+            for x in 0..WIDTH as u8 {
+                cells.push(CoordXY::new(x, y).unwrap()).unwrap();
+            }
+        }
+        assert_eq!(
+            graph.maze.reachable(&graph, graph.maze.start).len(),
+            open(&graph.maze, &cells)
+        );
+    }
+    #[test]
+    fn maze_branching_stats() {
+        let graph = four_way_grid::Graph {
+            maze: Maze::load_from_str(GOAL_MAZE_STR).unwrap(),
+        };
+        assert_eq!(graph.maze.degree(&graph, CoordXY::new(1, 1).unwrap()), 3);
+        assert_eq!(graph.maze.degree(&graph, CoordXY::new(3, 0).unwrap()), 1);
+        assert_eq!(
+            graph.maze.branching_stats(&graph),
+            BranchingStats {
+                junctions: 3,
+                average_degree_x100: 200,
+            }
+        );
+    }
+    #[test]
+    fn maze_diameter_endpoints() {
+        let graph = four_way_grid::Graph {
+            maze: Maze::load_from_str(GOAL_MAZE_STR).unwrap(),
+        };
+        assert_eq!(
+            graph.maze.diameter_endpoints(&graph),
+            Some((CoordXY::new(3, 0).unwrap(), CoordXY::new(1, 3).unwrap(), 7))
+        );
+        let mut graph = open_grid();
+        graph.maze.start = CoordXY::new(4, 4).unwrap();
+        for direction in [
+            Direction::North,
+            Direction::East,
+            Direction::South,
+            Direction::West,
+        ] {
+            graph.maze.set_cell_state(graph.maze.start, direction, true);
+        }
+        assert_eq!(
+            graph.maze.diameter_endpoints(&graph),
+            Some((graph.maze.start, graph.maze.start, 0))
+        );
+    }
+    #[test]
+    fn maze_route_count() {
+        // One loop around the wall between (2, 2) and (3, 2)
+        let graph = four_way_grid::Graph {
+            maze: Maze::load_from_str(GOAL_MAZE_STR).unwrap(),
+        };
+        assert_eq!(graph.maze.route_count(&graph), 2);
+
+        // Closing the loop leaves a perfect maze
+        let mut graph = graph;
+        graph
+            .maze
+            .set_cell_state(CoordXY::new(2, 2).unwrap(), Direction::North, true);
+        assert_eq!(graph.maze.route_count(&graph), 1);
+
+        graph
+            .maze
+            .set_cell_state(CoordXY::new(1, 0).unwrap(), Direction::North, true);
+        assert_eq!(graph.maze.route_count(&graph), 0);
+
+        // Far too many routes to enumerate
+        let graph = open_grid();
+        assert_eq!(graph.maze.route_count(&graph), u32::MAX);
+    }
+    #[test]
+    fn maze_min_cut_to_goal() {
+        // Two rooms joined by a single passage east of (1, 2)
+        let maze = Maze::load_from_str(
+            "\
+            +---+---+---+---+\n\
+            |       |     G |\n\
+            +   +   +   +   +\n\
+            |               |\n\
+            +   +   +   +   +\n\
+            |       |       |\n\
+            +   +   +   +   +\n\
+            | S     |       |\n\
+            +---+---+---+---+\n",
+        )
+        .unwrap();
+        let graph = four_way_grid::Graph { maze };
+        assert_eq!(
+            graph.maze.min_cut_to_goal(&graph),
+            [(CoordXY::new(1, 2).unwrap(), Direction::East)]
+        );
+
+        // Separated by a wall already
+        let mut graph = graph;
+        graph
+            .maze
+            .set_cell_state(CoordXY::new(1, 2).unwrap(), Direction::East, true);
+        assert!(graph.maze.min_cut_to_goal(&graph).is_empty());
+
+        // Two disjoint routes around an open grid corner
+        let graph = open_grid();
+        assert_eq!(
+            graph.maze.min_cut_to_goal(&graph),
+            [
+                (CoordXY::new(0, 0).unwrap(), Direction::North),
+                (CoordXY::new(0, 0).unwrap(), Direction::East),
+            ]
+        );
+    }
+    #[test]
+    fn route_direction_path_round_trip() {
+        use Direction::*;
+        let graph = open_grid();
+        let route = grid_route(&[(1, 1), (2, 1), (2, 2), (1, 2), (1, 3)]);
+        let (start, directions) = route.to_direction_path(&graph).unwrap();
+        assert_eq!(start, route.nodes()[0]);
+        assert_eq!(directions, [East, North, West, North]);
+        assert_eq!(
+            Route::from_direction_path(&graph, start, &directions).unwrap(),
+            route
+        );
+        assert_eq!(
+            Route::<four_way_grid::Graph>::new(Vec::new(), 0)
+                .to_direction_path(&graph)
+                .err(),
+            Some(Error::InvalidLocation)
+        );
+    }
+    #[test]
+    fn route_from_direction_path_blocked() {
+        use Direction::*;
+        let mut graph = open_grid();
+        let start = grid_route(&[(0, 0)]).nodes()[0];
+        assert_eq!(
+            Route::from_direction_path(&graph, start, &[South]).err(),
+            Some(Error::OutOfRange)
+        );
+        graph
+            .maze
+            .set_cell_state(CoordXY::new(0, 1).unwrap(), East, true);
+        assert_eq!(
+            Route::from_direction_path(&graph, start, &[North, East]).err(),
+            Some(Error::InvalidLocation)
+        );
+    }
+    #[test]
+    fn route_to_commands() {
+        use Direction::*;
+        let route = grid_route(&[(0, 0), (0, 1), (0, 2), (1, 2), (1, 1)]);
+        assert_eq!(
+            route.to_commands(North),
+            [
+                Command::Forward(2),
+                Command::Turn(Turn::Right),
+                Command::Forward(1),
+                Command::Turn(Turn::Right),
+                Command::Forward(1),
+            ]
+        );
+        assert_eq!(
+            route.to_commands(South)[..2],
+            [Command::Turn(Turn::Back), Command::Forward(2)]
+        );
+        assert!(grid_route(&[(0, 0)]).to_commands(North).is_empty());
+    }
+    #[test]
+    fn route_command_count() {
+        use Direction::*;
+        for route in [
+            grid_route(&[(0, 0)]),
+            grid_route(&[(0, 0), (1, 0), (2, 0)]),
+            grid_route(&[(0, 0), (0, 1), (0, 2), (1, 2), (1, 1)]),
+            grid_route(&[(1, 1), (1, 2), (1, 1), (0, 1)]),
+        ] {
+            for heading in [North, East, South, West] {
+                assert_eq!(
+                    route.command_count(heading),
+                    route.to_commands(heading).len()
+                );
+            }
+        }
+    }
+    #[test]
+    fn route_min_clearance() {
+        let graph = four_way_grid::Graph {
+            maze: Maze::load_from_str(GOAL_MAZE_STR).unwrap(),
+        };
+        // Along the corridors at the bottom, one of which ends at a dead end
+        let corridor = grid_route(&[(1, 0), (2, 0), (3, 0)]);
+        assert_eq!(corridor.min_clearance(&graph.maze), 1);
+        let corridor = grid_route(&[(1, 0), (2, 0)]);
+        assert_eq!(corridor.min_clearance(&graph.maze), 2);
+        // Through the open middle of the maze
+        let open = grid_route(&[(1, 0), (1, 1), (1, 2)]);
+        assert_eq!(open.min_clearance(&graph.maze), 3);
+        assert!(open.min_clearance(&graph.maze) > corridor.min_clearance(&graph.maze));
+        let empty = Route::<four_way_grid::Graph>::new(Vec::new(), 0);
+        assert_eq!(empty.min_clearance(&graph.maze), 0);
+    }
+    #[test]
+    fn route_turn_count() {
+        assert_eq!(
+            grid_route(&[(0, 0), (1, 0), (2, 0), (3, 0)]).turn_count(),
+            0
+        );
+        assert_eq!(
+            grid_route(&[(0, 0), (1, 0), (1, 1), (1, 2)]).turn_count(),
+            1
+        );
+        assert_eq!(
+            grid_route(&[(0, 0), (1, 0), (1, 1), (2, 1)]).turn_count(),
+            2
+        );
+        assert_eq!(grid_route(&[(0, 0)]).turn_count(), 0);
+    }
+    #[test]
+    fn route_dominates() {
+        let straight = grid_route(&[(0, 0), (1, 0), (2, 0)]);
+        let detour = grid_route(&[(0, 0), (0, 1), (1, 1), (1, 0), (2, 0)]);
+        assert!(straight.dominates(&detour));
+        assert!(!detour.dominates(&straight));
+
+        let one_turn = grid_route(&[(0, 0), (0, 1), (1, 1), (2, 1)]);
+        let two_turns = grid_route(&[(0, 0), (1, 0), (1, 1), (2, 1)]);
+        assert!(one_turn.dominates(&two_turns));
+        assert!(!two_turns.dominates(&one_turn));
+    }
+    #[test]
+    fn route_dominates_neither() {
+        let short = grid_route(&[(0, 0), (1, 0), (1, 1), (2, 1)]);
+        let straight = grid_route(&[(0, 0), (1, 0), (2, 0), (3, 0), (4, 0)]);
+        assert!(!short.dominates(&straight));
+        assert!(!straight.dominates(&short));
+        // A route never dominates itself
+        assert!(!short.dominates(&short));
+    }
+    #[test]
+    fn route_iter() {
+        let nodes: Vec<NodeIndex<four_way_grid::Graph>, { WIDTH * WIDTH }> = [0, 1, WIDTH + 1]
+            .iter()
+            .map(|&i| NodeIndex::new(i as NodeIndexValue).unwrap())
+            .collect();
+        let route = Route::new(nodes.clone(), 2);
+        assert_eq!(route.len(), 3);
+        assert!(!route.is_empty());
+        assert_eq!(route.cost(), 2);
+        assert!(route.iter().eq(nodes.iter()));
+        assert_eq!(route.iter().nth(2), Some(&nodes[2]));
+
+        let empty = Route::<four_way_grid::Graph>::new(Vec::new(), 0);
+        assert_eq!(empty.len(), 0);
+        assert!(empty.is_empty());
+        assert_eq!(empty.iter().next(), None);
+    }
+    #[test]
+    fn route_edges() {
+        let g = open_grid();
+        let route = grid_route(&[(0, 0), (1, 0), (1, 1)]);
+        let edges: Vec<Edge<four_way_grid::Graph>, 4> = route.edges(&g).collect();
+        assert_eq!(edges.len(), 2);
+        assert_eq!(edges[0].from(), route.nodes()[0]);
+        assert_eq!(edges[0].to(), route.nodes()[1]);
+        assert_eq!(edges[1].from(), route.nodes()[1]);
+        assert_eq!(edges[1].to(), route.nodes()[2]);
+        assert_eq!(edges.iter().map(|e| e.cost()).sum::<Cost>(), route.cost());
+
+        // The iteration stops where the route is broken
+        let broken = grid_route(&[(0, 0), (1, 0), (3, 0), (4, 0)]);
+        assert_eq!(broken.edges(&g).count(), 1);
+    }
+    #[test]
+    fn route_agent_states() {
+        let route = grid_route(&[(0, 0), (1, 0), (1, 1)]);
+        let states: Vec<AgentState, 4> = route.agent_states().collect();
+        assert_eq!(states.len(), 3);
+        assert_eq!(states[0].location, CoordXY::new(0, 0).unwrap());
+        assert_eq!(states[0].heading_vector, VectorXY { x: 0, y: 0 });
+        assert_eq!(states[1].location, CoordXY::new(1, 0).unwrap());
+        assert_eq!(states[1].heading_vector, Direction::East.into());
+        assert_eq!(states[2].location, CoordXY::new(1, 1).unwrap());
+        assert_eq!(states[2].heading_vector, Direction::North.into());
+    }
+    #[test]
+    fn route_unconfirmed_edges() {
+        // Only the start cell has been sensed so far
+        let mut maze = Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(3, 0).unwrap());
+        for direction in [Direction::North, Direction::East] {
+            maze.set_cell_check(CoordXY::new(0, 0).unwrap(), direction, true);
+        }
+        maze.set_cell_state(CoordXY::new(0, 0).unwrap(), Direction::North, true);
+        let graph = four_way_grid::Graph { maze };
+        let route = crate::solver::optimistic::optimistic_route(
+            &graph.maze,
+            &graph,
+            node_index_at(CoordXY::new(0, 0).unwrap()).unwrap(),
+            node_index_at(CoordXY::new(3, 0).unwrap()).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(route.cost(), 3);
+        assert_eq!(
+            route.unconfirmed_edges(&graph.maze),
+            [
+                (CoordXY::new(1, 0).unwrap(), Direction::East),
+                (CoordXY::new(2, 0).unwrap(), Direction::East)
+            ]
+        );
+
+        // Everything is confirmed once the rest of the row is sensed
+        let mut maze = graph.maze;
+        for x in 1..3 {
+            maze.set_cell_check(CoordXY::new(x, 0).unwrap(), Direction::East, true);
+        }
+        assert!(route.unconfirmed_edges(&maze).is_empty());
+    }
 }
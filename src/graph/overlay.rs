@@ -0,0 +1,223 @@
+#![forbid(unsafe_code)]
+
+use crate::graph::*;
+
+pub const MAX_VIRTUAL_WALLS: usize = 16;
+
+/// A view of `base` with a few walls added or removed without touching the underlying maze.
+/// Walls are given as pairs of adjacent nodes and apply in both directions.
+pub struct OverlayGraph<'a, T: GraphBase> {
+    pub base: &'a T,
+    added: Vec<(NodeIndexValue, NodeIndexValue), MAX_VIRTUAL_WALLS>,
+    removed: Vec<(NodeIndexValue, NodeIndexValue), MAX_VIRTUAL_WALLS>,
+}
+impl<'a, T: GraphBase> OverlayGraph<'a, T> {
+    pub fn new(base: &'a T) -> Self {
+        Self {
+            base,
+            added: Vec::new(),
+            removed: Vec::new(),
+        }
+    }
+    #[inline]
+    fn to_base(index: NodeIndex<Self>) -> NodeIndex<T> {
+        index.transmute_to()
+    }
+    #[inline]
+    fn from_base(index: NodeIndex<T>) -> NodeIndex<Self> {
+        index.transmute_to()
+    }
+    #[inline]
+    fn key(a: NodeIndex<T>, b: NodeIndex<T>) -> (NodeIndexValue, NodeIndexValue) {
+        (a.value.min(b.value), a.value.max(b.value))
+    }
+    // Walls separate the cells of two nodes which share a side
+    fn check_adjacent(a: NodeIndex<T>, b: NodeIndex<T>) -> Result<(), Error> {
+        let vector = b.to_agent_state(None).location - a.to_agent_state(None).location;
+        Direction::try_from(vector).map(|_| ())
+    }
+    /// Blocks the edge between `a` and `b`. Fails with `Error::InvalidVector` if the two nodes
+    /// are not adjacent, or with `Error::OutOfRange` if there are already `MAX_VIRTUAL_WALLS`
+    /// walls added.
+    pub fn add_wall(&mut self, a: NodeIndex<T>, b: NodeIndex<T>) -> Result<(), Error> {
+        Self::check_adjacent(a, b)?;
+        let key = Self::key(a, b);
+        self.removed.retain(|&k| k != key);
+        if !self.added.contains(&key) {
+            self.added.push(key).map_err(|_| Error::OutOfRange)?;
+        }
+        Ok(())
+    }
+    /// Opens the edge between `a` and `b`. Fails with `Error::InvalidVector` if the two nodes
+    /// are not adjacent, or with `Error::OutOfRange` if there are already `MAX_VIRTUAL_WALLS`
+    /// walls removed.
+    pub fn remove_wall(&mut self, a: NodeIndex<T>, b: NodeIndex<T>) -> Result<(), Error> {
+        Self::check_adjacent(a, b)?;
+        let key = Self::key(a, b);
+        self.added.retain(|&k| k != key);
+        if !self.removed.contains(&key) {
+            self.removed.push(key).map_err(|_| Error::OutOfRange)?;
+        }
+        Ok(())
+    }
+}
+impl<T: GraphBase> SharedIndexSpace<T> for OverlayGraph<'_, T> {}
+impl<T: GraphBase> SharedIndexSpace<OverlayGraph<'_, T>> for T {}
+impl<T: GraphBase> GraphBase for OverlayGraph<'_, T> {
+    const MAX_NODE_INDEX: NodeIndexValue = T::MAX_NODE_INDEX;
+    const COST_SCALE: Cost = T::COST_SCALE;
+    fn cost(from: NodeIndex<Self>, to: NodeIndex<Self>) -> Cost {
+        T::cost(Self::to_base(from), Self::to_base(to))
+    }
+    fn optimistic_cost(from: NodeIndex<Self>, to: NodeIndex<Self>) -> Cost {
+        T::optimistic_cost(Self::to_base(from), Self::to_base(to))
+    }
+    fn agent_state_by_node_index(
+        index: NodeIndex<Self>,
+        from_index: Option<NodeIndex<Self>>,
+    ) -> AgentState {
+        T::agent_state_by_node_index(Self::to_base(index), from_index.map(Self::to_base))
+    }
+    fn node_index_by_agent_state(agent_state: AgentState) -> Result<NodeIndex<Self>, Error> {
+        T::node_index_by_agent_state(agent_state).map(Self::from_base)
+    }
+    fn turn_cost(from_heading: VectorXY, edge: &Edge<Self>) -> Cost {
+        T::turn_cost(
+            from_heading,
+            &Edge::new(Self::to_base(edge.from()), Self::to_base(edge.to())),
+        )
+    }
+    fn neighbors(&self, from: NodeIndex<Self>) -> Vec<Edge<Self>, MAX_NEIGHBORS> {
+        let base_from = Self::to_base(from);
+        let mut vec: Vec<Edge<Self>, MAX_NEIGHBORS> = self
+            .base
+            .neighbors(base_from)
+            .iter()
+            .filter(|edge| !self.added.contains(&Self::key(base_from, edge.to)))
+            .map(|edge| Edge::new(from, Self::from_base(edge.to)))
+            .collect();
+        for &(a, b) in self.removed.iter() {
+            let to = match from.value {
+                v if v == a => b,
+                v if v == b => a,
+                _ => continue,
+            };
+            let to = NodeIndex::new(to).unwrap();
+            if !vec.iter().any(|e| e.to == to) {
+                // NOTE: removed walls only lead to adjacent nodes, which the base graph leaves
+                // room for
+                debug_assert!(vec.len() < vec.capacity());
+                vec.push(Edge::new(from, to)).ok();
+            }
+        }
+        vec
+    }
+    fn edge(&self, from: NodeIndex<Self>, to: NodeIndex<Self>) -> Option<Edge<Self>> {
+        let key = Self::key(Self::to_base(from), Self::to_base(to));
+        if self.added.contains(&key) {
+            None
+        } else if self.removed.contains(&key) {
+            Some(Edge::new(from, to))
+        } else {
+            self.base
+                .edge(Self::to_base(from), Self::to_base(to))
+                .map(|_| Edge::new(from, to))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::four_way_grid::{index, Graph};
+    use crate::solver::astar::astar;
+
+    #[test]
+    fn overlay_add_wall_detours() {
+        let base = Graph {
+            maze: Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(3, 0).unwrap()),
+        };
+        let mut overlay = OverlayGraph::new(&base);
+        overlay.add_wall(index(2, 0), index(1, 0)).unwrap();
+        assert!(overlay.edge(index(1, 0), index(2, 0)).is_none());
+        assert_eq!(overlay.neighbors(index(1, 0)).len(), 2);
+
+        let route = astar(&overlay, index(0, 0), index(3, 0)).unwrap();
+        assert_eq!(route.cost(), 5);
+        assert_eq!(astar(&base, index(0, 0), index(3, 0)).unwrap().cost(), 3);
+        assert!(!base
+            .maze
+            .cell(CoordXY::new(1, 0).unwrap())
+            .state_by_direction(Direction::East));
+    }
+    #[test]
+    fn overlay_remove_wall_shortcuts() {
+        let mut base = Graph {
+            maze: Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(1, 0).unwrap()),
+        };
+        base.maze
+            .set_cell_state(CoordXY::new(0, 0).unwrap(), Direction::East, true);
+        let mut overlay = OverlayGraph::new(&base);
+        overlay.remove_wall(index(0, 0), index(1, 0)).unwrap();
+        assert_eq!(overlay.neighbors(index(0, 0)).len(), 2);
+        assert_eq!(overlay.neighbors(index(1, 0)).len(), 3);
+        let route = astar(&overlay, index(0, 0), index(1, 0)).unwrap();
+        assert_eq!(route.cost(), 1);
+        assert_eq!(astar(&base, index(0, 0), index(1, 0)).unwrap().cost(), 3);
+
+        overlay.add_wall(index(1, 0), index(0, 0)).unwrap();
+        assert!(overlay.edge(index(0, 0), index(1, 0)).is_none());
+    }
+    #[test]
+    fn overlay_transmute_index() {
+        let base = Graph {
+            maze: Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(1, 0).unwrap()),
+        };
+        let overlay = OverlayGraph::new(&base);
+        let node: NodeIndex<Graph> = index(2, 3);
+        let converted: NodeIndex<OverlayGraph<Graph>> = node.transmute_to();
+        assert_eq!(converted.value(), node.value());
+        assert_eq!(converted.to_agent_state(None), node.to_agent_state(None));
+        assert_eq!(
+            overlay.neighbors(converted).len(),
+            base.neighbors(node).len()
+        );
+        assert_eq!(converted.transmute_to::<Graph>(), node);
+    }
+    #[test]
+    fn overlay_not_adjacent() {
+        let base = Graph {
+            maze: Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(1, 0).unwrap()),
+        };
+        let mut overlay = OverlayGraph::new(&base);
+        for (a, b) in [((0, 0), (2, 0)), ((0, 0), (1, 1)), ((1, 1), (1, 1))] {
+            let (a, b) = (index(a.0, a.1), index(b.0, b.1));
+            assert_eq!(overlay.remove_wall(a, b), Err(Error::InvalidVector));
+            assert_eq!(overlay.add_wall(a, b), Err(Error::InvalidVector));
+            assert!(overlay.edge(a.transmute_to(), b.transmute_to()).is_none());
+        }
+        // Consecutive indices across the end of a row are not adjacent
+        let last = WIDTH as u8 - 1;
+        assert_eq!(
+            overlay.remove_wall(index(last, 0), index(0, 1)),
+            Err(Error::InvalidVector)
+        );
+    }
+    #[test]
+    fn overlay_capacity() {
+        let base = Graph {
+            maze: Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(1, 0).unwrap()),
+        };
+        let mut overlay = OverlayGraph::new(&base);
+        for i in 0..MAX_VIRTUAL_WALLS {
+            let (x, y) = ((i % WIDTH) as u8, (i / WIDTH) as u8);
+            overlay.add_wall(index(x, y), index(x, y + 1)).unwrap();
+        }
+        // Adding the same wall twice does not take up space
+        overlay.add_wall(index(0, 1), index(0, 0)).unwrap();
+        assert_eq!(
+            overlay.add_wall(index(0, 0), index(1, 0)).err(),
+            Some(Error::OutOfRange)
+        );
+    }
+}
@@ -1,5 +1,12 @@
 #![no_std]
 #![forbid(unsafe_code)]
 
+#[cfg(feature = "std")]
+extern crate std;
+
 pub mod types;
 pub mod graph;
+pub mod solver;
+pub mod generator;
+#[cfg(feature = "std")]
+pub mod svg;
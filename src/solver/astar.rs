@@ -0,0 +1,812 @@
+#![forbid(unsafe_code)]
+
+use heapless::binary_heap::{BinaryHeap, Min};
+
+use crate::graph::*;
+use crate::solver::*;
+
+/// Finds the minimum-cost route from `start` to `goal` guided by `T::optimistic_cost`, or `None`
+/// if the goal cannot be reached. The route is optimal as long as the heuristic never
+/// overestimates the remaining cost.
+pub fn astar<T: GraphBase>(graph: &T, start: NodeIndex<T>, goal: NodeIndex<T>) -> Option<Route<T>> {
+    astar_filtered(graph, start, goal, |_| true)
+}
+
+// A* search which only follows the edges accepted by `filter`
+pub(crate) fn astar_filtered<T: GraphBase, F: Fn(&Edge<T>) -> bool>(
+    graph: &T,
+    start: NodeIndex<T>,
+    goal: NodeIndex<T>,
+    filter: F,
+) -> Option<Route<T>> {
+    let mut open = BinaryHeap::<(Cost, NodeIndexValue), Min, MAX_NODES>::new();
+    let mut closed = NodeSet::new();
+    let mut costs = [Cost::MAX; MAX_NODES];
+    let mut parents = [-1 as NodeIndexValue; MAX_NODES];
+
+    costs[start.value() as usize] = 0;
+    open.push((T::optimistic_cost(start, goal), start.value()))
+        .ok()?;
+    while let Some((_, value)) = open.pop() {
+        if closed.contains(value) {
+            continue;
+        }
+        let current = NodeIndex::new(value).ok()?;
+        if current == goal {
+            return reconstruct_route(&parents, start, goal, costs[value as usize]);
+        }
+        closed.insert(value);
+        for edge in graph.neighbors(current).into_iter().filter(&filter) {
+            let next = edge.to().value();
+            let cost = costs[value as usize] + edge.cost();
+            if closed.contains(next) || cost >= costs[next as usize] {
+                continue;
+            }
+            costs[next as usize] = cost;
+            parents[next as usize] = value;
+            let f = cost + T::optimistic_cost(edge.to(), goal);
+            push_compacting(&mut open, (f, next), |entry| {
+                is_current(&closed, &costs, goal, entry)
+            })
+            .ok()?;
+        }
+    }
+    None
+}
+
+// Returns whether an open entry still holds the f-score of its node
+fn is_current<T: GraphBase>(
+    closed: &NodeSet,
+    costs: &[Cost; MAX_NODES],
+    goal: NodeIndex<T>,
+    &(f, v): &(Cost, NodeIndexValue),
+) -> bool {
+    !closed.contains(v)
+        && NodeIndex::new(v)
+            .is_ok_and(|node| f == costs[v as usize] + T::optimistic_cost(node, goal))
+}
+
+/// Chains the optimal routes between consecutive `waypoints` into a single route. Fails with
+/// `Error::NoRoute` if any leg cannot be completed and `Error::InvalidLocation` if no waypoint is
+/// given.
+pub fn route_through<T: GraphBase>(
+    graph: &T,
+    waypoints: &[NodeIndex<T>],
+) -> Result<Route<T>, Error> {
+    let (&first, _) = waypoints.split_first().ok_or(Error::InvalidLocation)?;
+    let mut route = Route::new(Vec::from_slice(&[first]).unwrap(), 0);
+    for pair in waypoints.windows(2) {
+        let leg = astar(graph, pair[0], pair[1]).ok_or(Error::NoRoute)?;
+        route = route.concat(&leg)?;
+    }
+    Ok(route)
+}
+
+/// Open node considered for expansion by `astar_with_tiebreak`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Candidate<T: GraphBase> {
+    pub node: NodeIndex<T>,
+    /// Cost of the best route found so far from the start to `node`.
+    pub cost: Cost,
+}
+
+/// Orders the open nodes of equal f-score in `astar_with_tiebreak`. Closures comparing two
+/// candidates implement this as well.
+pub trait TieBreak<T: GraphBase> {
+    /// Returns `Ordering::Less` if `a` should be expanded before `b`.
+    fn cmp(&self, a: &Candidate<T>, b: &Candidate<T>) -> core::cmp::Ordering;
+}
+impl<T: GraphBase, F: Fn(&Candidate<T>, &Candidate<T>) -> core::cmp::Ordering> TieBreak<T> for F {
+    fn cmp(&self, a: &Candidate<T>, b: &Candidate<T>) -> core::cmp::Ordering {
+        self(a, b)
+    }
+}
+
+/// Expands the candidate farthest from the start first, which keeps following the current
+/// direction and so tends to give straighter routes.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct HighGFirst;
+impl<T: GraphBase> TieBreak<T> for HighGFirst {
+    fn cmp(&self, a: &Candidate<T>, b: &Candidate<T>) -> core::cmp::Ordering {
+        b.cost.cmp(&a.cost)
+    }
+}
+
+/// Same as `astar`, but lets `tiebreak` choose among the open nodes of the lowest f-score. Which
+/// of several optimal routes is returned depends on it; the cost does not.
+pub fn astar_with_tiebreak<T: GraphBase, C: TieBreak<T>>(
+    graph: &T,
+    start: NodeIndex<T>,
+    goal: NodeIndex<T>,
+    tiebreak: C,
+) -> Option<Route<T>> {
+    let mut open = BinaryHeap::<(Cost, NodeIndexValue), Min, MAX_NODES>::new();
+    let mut ties = Vec::<(Cost, NodeIndexValue), MAX_NODES>::new();
+    let mut closed = NodeSet::new();
+    let mut costs = [Cost::MAX; MAX_NODES];
+    let mut parents = [-1 as NodeIndexValue; MAX_NODES];
+    let candidate = |value: NodeIndexValue, costs: &[Cost; MAX_NODES]| {
+        NodeIndex::new(value).map(|node| Candidate {
+            node,
+            cost: costs[value as usize],
+        })
+    };
+
+    costs[start.value() as usize] = 0;
+    open.push((T::optimistic_cost(start, goal), start.value()))
+        .ok()?;
+    while let Some((f, mut value)) = open.pop() {
+        if closed.contains(value) {
+            continue;
+        }
+        // NOTE: pick the best of the open nodes sharing the lowest f-score and put the rest back
+        while open.peek().is_some_and(|&(g, _)| g == f) {
+            let (_, other) = open.pop()?;
+            if closed.contains(other) || other == value {
+                continue;
+            }
+            let (a, b) = (
+                candidate(other, &costs).ok()?,
+                candidate(value, &costs).ok()?,
+            );
+            if tiebreak.cmp(&a, &b) == core::cmp::Ordering::Less {
+                ties.push((f, value)).ok()?;
+                value = other;
+            } else {
+                ties.push((f, other)).ok()?;
+            }
+        }
+        while let Some(entry) = ties.pop() {
+            open.push(entry).ok()?;
+        }
+        let current = NodeIndex::new(value).ok()?;
+        if current == goal {
+            return reconstruct_route(&parents, start, goal, costs[value as usize]);
+        }
+        closed.insert(value);
+        for edge in graph.neighbors(current) {
+            let next = edge.to().value();
+            let cost = costs[value as usize] + edge.cost();
+            if closed.contains(next) || cost >= costs[next as usize] {
+                continue;
+            }
+            costs[next as usize] = cost;
+            parents[next as usize] = value;
+            let f = cost + T::optimistic_cost(edge.to(), goal);
+            push_compacting(&mut open, (f, next), |entry| {
+                is_current(&closed, &costs, goal, entry)
+            })
+            .ok()?;
+        }
+    }
+    None
+}
+
+// Headings are tracked by the signs of their components, i.e. as one of 9 compass points
+const HEADINGS: usize = 9;
+const MAX_STATES: usize = MAX_NODES * HEADINGS;
+
+#[inline]
+fn heading_index(heading: VectorXY) -> usize {
+    (heading.x.signum() + 1) as usize * 3 + (heading.y.signum() + 1) as usize
+}
+#[inline]
+fn heading_vector(h: usize) -> VectorXY {
+    VectorXY {
+        x: (h / 3) as i8 - 1,
+        y: (h % 3) as i8 - 1,
+    }
+}
+
+/// A* search in which the agent starts at `start` heading along `heading`, and every edge costs
+/// `T::turn_cost` on top of its own cost depending on the heading it is entered with.
+///
+/// The search keeps a cost, a parent and an open-list entry on the stack for each node and each
+/// of 9 headings, which takes about 135 KB with `maze_32x32` and 8 KB with `maze_8x8`.
+pub fn astar_from_state<T: GraphBase>(
+    graph: &T,
+    start: NodeIndex<T>,
+    heading: VectorXY,
+    goal: NodeIndex<T>,
+) -> Option<Route<T>> {
+    // NOTE: a state is a node together with the heading the agent has there
+    let state = |node: NodeIndex<T>, heading: VectorXY| {
+        node.value() as usize * HEADINGS + heading_index(heading)
+    };
+    let mut open = BinaryHeap::<(Cost, u16), Min, MAX_STATES>::new();
+    let mut closed = [false; MAX_STATES];
+    let mut costs = [Cost::MAX; MAX_STATES];
+    let mut parents = [u16::MAX; MAX_STATES];
+
+    let initial = state(start, heading);
+    costs[initial] = 0;
+    open.push((T::optimistic_cost(start, goal), initial as u16))
+        .ok()?;
+    while let Some((_, current)) = open.pop() {
+        let current = current as usize;
+        if closed[current] {
+            continue;
+        }
+        let node = NodeIndex::<T>::new((current / HEADINGS) as NodeIndexValue).ok()?;
+        if node == goal {
+            let mut nodes = Vec::<NodeIndex<T>, MAX_NODES>::new();
+            let mut s = current;
+            loop {
+                nodes
+                    .push(NodeIndex::new((s / HEADINGS) as NodeIndexValue).ok()?)
+                    .ok()?;
+                if s == initial {
+                    break;
+                }
+                s = parents[s] as usize;
+            }
+            nodes.reverse();
+            return Some(Route::new(nodes, costs[current]));
+        }
+        closed[current] = true;
+        for edge in graph.neighbors(node) {
+            let next = state(edge.to(), edge.agent_state_at_to().heading_vector);
+            let cost = costs[current] + step_cost(current % HEADINGS, &edge);
+            if closed[next] || cost >= costs[next] {
+                continue;
+            }
+            costs[next] = cost;
+            parents[next] = current as u16;
+            let f = cost + T::optimistic_cost(edge.to(), goal);
+            push_compacting(&mut open, (f, next as u16), |&(f, s)| {
+                let s = s as usize;
+                !closed[s]
+                    && NodeIndex::new((s / HEADINGS) as NodeIndexValue)
+                        .is_ok_and(|node| f == costs[s] + T::optimistic_cost(node, goal))
+            })
+            .ok()?;
+        }
+    }
+    None
+}
+
+/// Finds a minimum-cost route from `start` to `goal` whose longest straight run is as long as
+/// possible, or `None` if the goal cannot be reached. Edge costs are assumed to be the same in
+/// both directions.
+pub fn astar_max_straight<T: GraphBase>(
+    graph: &T,
+    start: NodeIndex<T>,
+    goal: NodeIndex<T>,
+) -> Option<Route<T>> {
+    use crate::solver::flood::flood_fill;
+
+    let to_goal = flood_fill(graph, goal);
+    // NOTE: the costs are symmetric, so the costs to `start` are the costs from it as well
+    let from_start = flood_fill(graph, start);
+    let optimal = to_goal[start.value() as usize];
+    if optimal == Cost::MAX {
+        return None;
+    }
+    // Nodes and edges on some minimum-cost route form a DAG ordered by the cost from the start
+    let on_route = |node: NodeIndex<T>| {
+        let (s, g) = (
+            from_start[node.value() as usize],
+            to_goal[node.value() as usize],
+        );
+        s != Cost::MAX && g != Cost::MAX && s + g == optimal
+    };
+    let on_route_edge = |from: NodeIndex<T>, to: NodeIndex<T>| {
+        on_route(from)
+            && on_route(to)
+            && graph.edge(from, to).is_some_and(|edge| {
+                from_start[from.value() as usize] + edge.cost() == from_start[to.value() as usize]
+            })
+    };
+    let mut nodes = Vec::<NodeIndex<T>, MAX_NODES>::new();
+    for value in 0..=T::MAX_NODE_INDEX {
+        let node = NodeIndex::<T>::new(value).ok()?;
+        if on_route(node) {
+            nodes.push(node).ok()?;
+        }
+    }
+    nodes.sort_unstable_by_key(|node| from_start[node.value() as usize]);
+
+    // Longest straight run of route edges ending at each node with each heading
+    let mut runs = [0u8; MAX_STATES];
+    let mut run_parents = [-1 as NodeIndexValue; MAX_STATES];
+    let mut best: Option<(usize, NodeIndex<T>)> = None;
+    for &node in nodes.iter() {
+        for edge in graph.neighbors(node) {
+            let from = edge.to();
+            if !on_route_edge(from, node) {
+                continue;
+            }
+            let h = heading_index(node.to_agent_state(Some(from)).heading_vector);
+            let state = node.value() as usize * HEADINGS + h;
+            let run = runs[from.value() as usize * HEADINGS + h] + 1;
+            if run > runs[state] {
+                runs[state] = run;
+                run_parents[state] = from.value();
+                if best.is_none_or(|(s, _)| run > runs[s]) {
+                    best = Some((state, node));
+                }
+            }
+        }
+    }
+
+    // The longest run itself, backward from its last node
+    let mut route_nodes = Vec::<NodeIndex<T>, MAX_NODES>::new();
+    let mut run_end = start;
+    if let Some((state, node)) = best {
+        run_end = node;
+        let h = state % HEADINGS;
+        let mut current = node;
+        route_nodes.push(current).ok()?;
+        while runs[current.value() as usize * HEADINGS + h] > 0 {
+            current = NodeIndex::new(run_parents[current.value() as usize * HEADINGS + h]).ok()?;
+            route_nodes.push(current).ok()?;
+        }
+    } else {
+        route_nodes.push(start).ok()?;
+    }
+    // Any route edges back to the start
+    let mut current = *route_nodes.last()?;
+    while current != start {
+        current = graph
+            .neighbors(current)
+            .iter()
+            .map(|edge| edge.to())
+            .find(|&from| on_route_edge(from, current))?;
+        route_nodes.push(current).ok()?;
+    }
+    route_nodes.reverse();
+    // Any route edges on to the goal
+    let mut current = run_end;
+    while current != goal {
+        current = graph
+            .neighbors(current)
+            .iter()
+            .map(|edge| edge.to())
+            .find(|&to| on_route_edge(current, to))?;
+        route_nodes.push(current).ok()?;
+    }
+    Some(Route::new(route_nodes, optimal))
+}
+
+// Heading index of the agent at `start` before its first move, which no heading is a turn from
+const NO_HEADING: usize = 4;
+
+// Cost of taking `edge` with the heading of index `from_h`, including that of any turn
+#[inline]
+fn step_cost<T: GraphBase>(from_h: usize, edge: &Edge<T>) -> Cost {
+    edge.cost() + T::turn_cost(heading_vector(from_h), edge)
+}
+
+// Costs of every state reachable with up to a certain number of turns
+type TurnLayer = [Cost; MAX_STATES];
+
+// Lowers the costs of `layer` by any moves that do not turn, until nothing changes
+fn extend_straight<T: GraphBase>(graph: &T, layer: &mut TurnLayer) {
+    let mut changed = true;
+    let mut forward = true;
+    while changed {
+        changed = false;
+        // NOTE: sweeping both ways carries the costs along runs in either direction quickly
+        for i in 0..=T::MAX_NODE_INDEX {
+            let value = if forward { i } else { T::MAX_NODE_INDEX - i };
+            let node = NodeIndex::<T>::new(value).unwrap();
+            for edge in graph.neighbors(node) {
+                let h = heading_index(edge.agent_state_at_to().heading_vector);
+                let next = edge.to().value() as usize * HEADINGS + h;
+                for from_h in [NO_HEADING, h] {
+                    let cost = layer[value as usize * HEADINGS + from_h];
+                    if cost != Cost::MAX && cost + step_cost(from_h, &edge) < layer[next] {
+                        layer[next] = cost + step_cost(from_h, &edge);
+                        changed = true;
+                    }
+                }
+            }
+        }
+        forward = !forward;
+    }
+}
+
+// Costs with up to one more turn than `prev`
+fn turn_once<T: GraphBase>(graph: &T, prev: &TurnLayer) -> TurnLayer {
+    let mut layer = *prev;
+    for value in 0..=T::MAX_NODE_INDEX {
+        let node = NodeIndex::<T>::new(value).unwrap();
+        for edge in graph.neighbors(node) {
+            let h = heading_index(edge.agent_state_at_to().heading_vector);
+            let next = edge.to().value() as usize * HEADINGS + h;
+            for from_h in (0..HEADINGS).filter(|&from_h| from_h != NO_HEADING && from_h != h) {
+                let cost = prev[value as usize * HEADINGS + from_h];
+                if cost != Cost::MAX && cost + step_cost(from_h, &edge) < layer[next] {
+                    layer[next] = cost + step_cost(from_h, &edge);
+                }
+            }
+        }
+    }
+    extend_straight(graph, &mut layer);
+    layer
+}
+
+// Costs with up to `turns - 1` and `turns` turns
+fn turn_layers<T: GraphBase>(
+    graph: &T,
+    start: NodeIndex<T>,
+    turns: usize,
+) -> (TurnLayer, TurnLayer) {
+    let mut prev = [Cost::MAX; MAX_STATES];
+    let mut layer = [Cost::MAX; MAX_STATES];
+    layer[start.value() as usize * HEADINGS + NO_HEADING] = 0;
+    extend_straight(graph, &mut layer);
+    for _ in 0..turns {
+        let next = turn_once(graph, &layer);
+        prev = layer;
+        layer = next;
+    }
+    (prev, layer)
+}
+
+/// Finds the minimum-cost route from `start` to `goal` which changes its heading at most
+/// `max_turns` times, e.g. for a robot that can only buffer so many turns. The cost includes
+/// `T::turn_cost` for every turn. Fails with `Error::NoRoute` if there is no such route.
+///
+/// The costs are kept by the number of turns in layers of 36 KB each with `maze_32x32` (2.25 KB
+/// with `maze_8x8`), up to five of which are on the stack at a time. Rather than keeping every
+/// layer, tracing the route back recomputes them from the start at each turn it passes, so a
+/// route with `n` turns costs about `n * n / 2` more passes over a layer than the search itself.
+/// This suits the few turns a robot can buffer, but gets slow for budgets of dozens of turns.
+pub fn astar_turn_limited<T: GraphBase>(
+    graph: &T,
+    start: NodeIndex<T>,
+    goal: NodeIndex<T>,
+    max_turns: usize,
+) -> Result<Route<T>, Error> {
+    let goal_state = |layer: &TurnLayer| {
+        (0..HEADINGS)
+            .map(|h| goal.value() as usize * HEADINGS + h)
+            .min_by_key(|&s| layer[s])
+            .unwrap()
+    };
+    // NOTE: the search runs in layers by the number of turns, and stops early once another turn
+    // does not lower any cost since no further one can
+    let (_, mut layer) = turn_layers(graph, start, 0);
+    let mut best = (layer[goal_state(&layer)], 0);
+    for turns in 1..=max_turns {
+        let next = turn_once(graph, &layer);
+        if next == layer {
+            break;
+        }
+        layer = next;
+        let cost = layer[goal_state(&layer)];
+        if cost < best.0 {
+            best = (cost, turns);
+        }
+    }
+    let (cost, mut turns) = best;
+    if cost == Cost::MAX {
+        return Err(Error::NoRoute);
+    }
+
+    // Trace the route back, moving to the previous layer at every turn
+    // NOTE: the layers of fewer turns are recomputed instead of kept, which would not fit on the
+    // stack
+    let (mut prev, mut layer) = turn_layers(graph, start, turns);
+    let initial = start.value() as usize * HEADINGS + NO_HEADING;
+    let mut current = goal_state(&layer);
+    let mut nodes = Vec::<NodeIndex<T>, MAX_NODES>::new();
+    nodes.push(goal).map_err(|_| Error::OutOfRange)?;
+    while current != initial {
+        let node = NodeIndex::<T>::new((current / HEADINGS) as NodeIndexValue)?;
+        let h = current % HEADINGS;
+        let (previous, turned) = graph
+            .neighbors(node)
+            .iter()
+            .filter_map(|back| graph.edge(back.to(), node))
+            .filter(|edge| heading_index(edge.agent_state_at_to().heading_vector) == h)
+            .find_map(|edge| {
+                (0..HEADINGS).find_map(|from_h| {
+                    let s = edge.from().value() as usize * HEADINGS + from_h;
+                    let turned = from_h != NO_HEADING && from_h != h;
+                    let cost = if turned { prev[s] } else { layer[s] };
+                    (cost != Cost::MAX && cost + step_cost(from_h, &edge) == layer[current])
+                        .then_some((s, turned))
+                })
+            })
+            .ok_or(Error::NoRoute)?;
+        if turned {
+            turns -= 1;
+            (prev, layer) = turn_layers(graph, start, turns);
+        }
+        current = previous;
+        nodes
+            .push(NodeIndex::new((current / HEADINGS) as NodeIndexValue)?)
+            .map_err(|_| Error::OutOfRange)?;
+    }
+    nodes.reverse();
+    Ok(Route::new(nodes, cost))
+}
+
+/// Finds the best route from `start` to `goal` for each initial heading, in the order of north,
+/// east, south and west. See `astar_from_state`.
+pub fn best_routes_by_heading<T: GraphBase>(
+    graph: &T,
+    start: NodeIndex<T>,
+    goal: NodeIndex<T>,
+) -> [Result<Route<T>, Error>; 4] {
+    use Direction::*;
+    [North, East, South, West].map(|direction| {
+        astar_from_state(graph, start, direction.into(), goal).ok_or(Error::NoRoute)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::four_way_grid::{index, Graph, MAZE_STR};
+
+    #[test]
+    fn astar_route() {
+        let g = Graph {
+            maze: Maze::load_from_str(MAZE_STR).unwrap(),
+        };
+        let route = astar(&g, index(0, 0), index(3, 2)).unwrap();
+        assert_eq!(route.cost(), 5);
+        assert_eq!(route.nodes().len(), 6);
+        assert_eq!(route.nodes().first(), Some(&index(0, 0)));
+        assert_eq!(route.nodes().last(), Some(&index(3, 2)));
+        for pair in route.nodes().windows(2) {
+            assert!(g.edge(pair[0], pair[1]).is_some());
+        }
+    }
+    #[test]
+    fn astar_from_state_heading() {
+        let g = Graph {
+            maze: Maze::load_from_str(MAZE_STR).unwrap(),
+        };
+        let start = index(0, 2);
+        let goal = index(3, 3);
+        // North into the top row and east along it, turning twice
+        let route = astar_from_state(&g, start, Direction::East.into(), goal).unwrap();
+        assert_eq!(route.cost(), 6);
+        assert_eq!(route.nodes().len(), 5);
+        // Without a heading the first edge is free of turn costs
+        let route = astar_from_state(&g, start, VectorXY { x: 0, y: 0 }, goal).unwrap();
+        assert_eq!(route.cost(), 5);
+        let unreachable = index(0, 0);
+        assert!(astar_from_state(&g, start, Direction::East.into(), unreachable).is_none());
+    }
+    #[test]
+    fn astar_from_state_fewer_turns() {
+        let g = Graph {
+            maze: Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(7, 7).unwrap()),
+        };
+        // Every monotone route to the goal is equally long, but the turns add up
+        let route =
+            astar_from_state(&g, index(0, 0), VectorXY { x: 0, y: 0 }, index(3, 3)).unwrap();
+        assert_eq!(route.turn_count(), 1);
+        assert_eq!(route.cost(), 6 + 1);
+        let route =
+            astar_from_state(&g, index(0, 0), Direction::North.into(), index(3, 3)).unwrap();
+        assert_eq!(route.turn_count(), 1);
+        assert_eq!(route.nodes()[1], index(0, 1));
+    }
+    #[test]
+    fn best_routes_by_heading_corridor() {
+        let g = Graph {
+            maze: Maze::load_from_str(MAZE_STR).unwrap(),
+        };
+        // The only way out of (0, 0) is the corridor to the east, which leads to the goal with a
+        // single left turn
+        let routes = best_routes_by_heading(&g, index(0, 0), index(3, 2));
+        let costs = routes.each_ref().map(|r| r.as_ref().unwrap().cost());
+        assert_eq!(costs, [7, 6, 7, 8]);
+        let east = routes[1].as_ref().unwrap();
+        assert_eq!(
+            east.nodes(),
+            [(0, 0), (1, 0), (2, 0), (3, 0), (3, 1), (3, 2)].map(|(x, y)| index(x, y))
+        );
+    }
+    #[test]
+    fn route_through_waypoints() {
+        let g = Graph {
+            maze: Maze::load_from_str(MAZE_STR).unwrap(),
+        };
+        let first = astar(&g, index(0, 0), index(3, 2)).unwrap();
+        let second = astar(&g, index(3, 2), index(1, 1)).unwrap();
+        let route = route_through(&g, &[index(0, 0), index(3, 2), index(1, 1)]).unwrap();
+        assert_eq!(route.cost(), first.cost() + second.cost());
+        assert_eq!(route.nodes().first(), Some(&index(0, 0)));
+        assert_eq!(route.nodes().last(), Some(&index(1, 1)));
+        assert_eq!(
+            route.nodes().len(),
+            first.nodes().len() + second.nodes().len() - 1
+        );
+        for pair in route.nodes().windows(2) {
+            assert!(g.edge(pair[0], pair[1]).is_some());
+        }
+
+        let route = route_through(&g, &[index(1, 1)]).unwrap();
+        assert_eq!(route.nodes(), &[index(1, 1)]);
+        assert_eq!(
+            route_through(&g, &[index(0, 0), index(0, 2)]).err(),
+            Some(Error::NoRoute)
+        );
+        assert_eq!(route_through(&g, &[]).err(), Some(Error::InvalidLocation));
+    }
+    #[test]
+    fn astar_matches_dijkstra() {
+        use crate::solver::dijkstra::dijkstra;
+        let g = Graph {
+            maze: Maze::load_from_str(MAZE_STR).unwrap(),
+        };
+        for start in [index(0, 0), index(0, 3), index(2, 1)] {
+            for y in 0..4 {
+                for x in 0..4 {
+                    let goal = index(x, y);
+                    let expected = dijkstra(&g, start, goal).map(|route| route.cost());
+                    assert_eq!(astar(&g, start, goal).map(|route| route.cost()), expected);
+                }
+            }
+        }
+    }
+    #[test]
+    fn astar_max_straight_longest_run() {
+        // Every monotone route from (0, 0) to (5, 2) costs 7. The fewest turns are taken by
+        // E2 N2 E3, but E1 N1 E4 N1 has a straight run of 4.
+        let mut maze = Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(5, 2).unwrap());
+        for (x, y) in [(2, 0), (0, 1), (1, 2)] {
+            maze.set_cell_state(CoordXY::new(x, y).unwrap(), Direction::East, true);
+        }
+        let g = Graph { maze };
+        let longest_run =
+            |route: &Route<Graph>| route.segments().iter().map(|segment| segment.length).max();
+        let route = astar_max_straight(&g, index(0, 0), index(5, 2)).unwrap();
+        assert_eq!(route.cost(), 7);
+        assert_eq!(
+            route.nodes(),
+            [
+                (0, 0),
+                (1, 0),
+                (1, 1),
+                (2, 1),
+                (3, 1),
+                (4, 1),
+                (5, 1),
+                (5, 2)
+            ]
+            .map(|(x, y)| index(x, y))
+        );
+        assert_eq!(longest_run(&route), Some(4));
+        assert_eq!(route.turn_count(), 3);
+
+        let min_turn = Route::new(
+            Vec::from_slice(
+                &[
+                    (0, 0),
+                    (1, 0),
+                    (2, 0),
+                    (2, 1),
+                    (2, 2),
+                    (3, 2),
+                    (4, 2),
+                    (5, 2),
+                ]
+                .map(|(x, y)| index(x, y)),
+            )
+            .unwrap(),
+            7,
+        );
+        assert!(min_turn.is_optimal(&g, index(0, 0), index(5, 2)));
+        assert_eq!(min_turn.turn_count(), 2);
+        assert_eq!(longest_run(&min_turn), Some(3));
+        let arbitrary = astar(&g, index(0, 0), index(5, 2)).unwrap();
+        assert!(longest_run(&arbitrary) < Some(4));
+
+        let route = astar_max_straight(&g, index(1, 1), index(1, 1)).unwrap();
+        assert_eq!(route.nodes(), &[index(1, 1)]);
+        let g = Graph {
+            maze: Maze::load_from_str(MAZE_STR).unwrap(),
+        };
+        assert!(astar_max_straight(&g, index(0, 0), index(0, 2)).is_none());
+    }
+    #[test]
+    fn astar_start_is_goal() {
+        let g = Graph {
+            maze: Maze::load_from_str(MAZE_STR).unwrap(),
+        };
+        let route = astar(&g, index(1, 1), index(1, 1)).unwrap();
+        assert_eq!(route.cost(), 0);
+        assert_eq!(route.nodes(), &[index(1, 1)]);
+    }
+    #[test]
+    fn astar_unreachable() {
+        let g = Graph {
+            maze: Maze::load_from_str(MAZE_STR).unwrap(),
+        };
+        // (0, 2) is only reachable from the top row, which is walled off from (0, 0)
+        assert!(astar(&g, index(0, 0), index(0, 2)).is_none());
+    }
+    #[test]
+    fn astar_turn_limited_budget() {
+        // The column from the start to the goal is blocked, and so is the shortest way around
+        // with two turns
+        let mut maze = Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(0, 3).unwrap());
+        maze.set_cell_state(CoordXY::new(0, 1).unwrap(), Direction::North, true);
+        maze.set_cell_state(CoordXY::new(1, 2).unwrap(), Direction::North, true);
+        let g = Graph { maze };
+        let shortest = astar(&g, index(0, 0), index(0, 3)).unwrap();
+        assert_eq!(shortest.cost(), 5);
+        assert!(shortest.turn_count() >= 3);
+
+        // Every turn costs 1 on top of the moves
+        let route = astar_turn_limited(&g, index(0, 0), index(0, 3), 3).unwrap();
+        assert_eq!(route.cost(), 5 + 3);
+        assert_eq!(route.turn_count(), 3);
+        // Fewer turns take a longer way around
+        let route = astar_turn_limited(&g, index(0, 0), index(0, 3), 2).unwrap();
+        assert_eq!(route.cost(), 7 + 2);
+        assert_eq!(route.turn_count(), 2);
+        assert_eq!(
+            route.nodes(),
+            [
+                (0, 0),
+                (1, 0),
+                (2, 0),
+                (2, 1),
+                (2, 2),
+                (2, 3),
+                (1, 3),
+                (0, 3)
+            ]
+            .map(|(x, y)| index(x, y))
+        );
+        for n in [0, 1] {
+            assert_eq!(
+                astar_turn_limited(&g, index(0, 0), index(0, 3), n).err(),
+                Some(Error::NoRoute)
+            );
+        }
+    }
+    #[test]
+    fn astar_turn_limited_straight() {
+        let g = Graph {
+            maze: Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(0, 3).unwrap()),
+        };
+        let route = astar_turn_limited(&g, index(0, 0), index(0, 3), 0).unwrap();
+        assert_eq!(route.cost(), 3);
+        assert_eq!(route.turn_count(), 0);
+        // No straight shot to a cell off the column
+        assert_eq!(
+            astar_turn_limited(&g, index(0, 0), index(1, 3), 0).err(),
+            Some(Error::NoRoute)
+        );
+        let route = astar_turn_limited(&g, index(0, 0), index(1, 3), 1).unwrap();
+        assert_eq!(route.cost(), 4 + 1);
+        assert_eq!(route.turn_count(), 1);
+        let route = astar_turn_limited(&g, index(2, 2), index(2, 2), 0).unwrap();
+        assert_eq!(route.nodes(), &[index(2, 2)]);
+    }
+    #[test]
+    fn astar_with_tiebreak_shapes() {
+        let g = Graph {
+            maze: Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(3, 3).unwrap()),
+        };
+        // Going deep first keeps heading east until the goal is straight ahead
+        let straight = astar_with_tiebreak(&g, index(0, 0), index(3, 3), HighGFirst).unwrap();
+        // Staying close to the diagonal gives a staircase instead
+        let diagonal = |a: &Candidate<Graph>, b: &Candidate<Graph>| {
+            let offset = |c: &Candidate<Graph>| {
+                let location = c.node.to_agent_state(None).location;
+                location.x().value().abs_diff(location.y().value())
+            };
+            offset(a).cmp(&offset(b))
+        };
+        let zigzag = astar_with_tiebreak(&g, index(0, 0), index(3, 3), diagonal).unwrap();
+        assert_eq!(straight.cost(), 6);
+        assert_eq!(zigzag.cost(), 6);
+        assert_eq!(straight.turn_count(), 1);
+        assert_eq!(zigzag.turn_count(), 5);
+        assert_eq!(
+            zigzag.nodes(),
+            [(0, 0), (1, 0), (1, 1), (2, 1), (2, 2), (3, 2), (3, 3)].map(|(x, y)| index(x, y))
+        );
+    }
+}
@@ -0,0 +1,230 @@
+#![forbid(unsafe_code)]
+
+use heapless::binary_heap::{BinaryHeap, Min};
+
+use crate::graph::*;
+use crate::solver::*;
+
+/// Finds the minimum-cost route from `start` to `goal`, or `None` if the goal cannot be reached.
+pub fn dijkstra<T: GraphBase>(
+    graph: &T,
+    start: NodeIndex<T>,
+    goal: NodeIndex<T>,
+) -> Option<Route<T>> {
+    let mut open = BinaryHeap::<(Cost, NodeIndexValue), Min, MAX_NODES>::new();
+    let mut closed = NodeSet::new();
+    let mut costs = [Cost::MAX; MAX_NODES];
+    let mut parents = [-1 as NodeIndexValue; MAX_NODES];
+
+    costs[start.value() as usize] = 0;
+    open.push((0, start.value())).ok()?;
+    while let Some((cost, value)) = open.pop() {
+        if closed.contains(value) {
+            continue;
+        }
+        let current = NodeIndex::new(value).ok()?;
+        if current == goal {
+            return reconstruct_route(&parents, start, goal, cost);
+        }
+        closed.insert(value);
+        for edge in graph.neighbors(current) {
+            let next = edge.to().value();
+            let next_cost = cost + edge.cost();
+            if closed.contains(next) || next_cost >= costs[next as usize] {
+                continue;
+            }
+            costs[next as usize] = next_cost;
+            parents[next as usize] = value;
+            push_compacting(&mut open, (next_cost, next), |&(c, v)| {
+                !closed.contains(v) && c == costs[v as usize]
+            })
+            .ok()?;
+        }
+    }
+    None
+}
+
+// One of the two searches of `bidirectional_dijkstra`
+struct Search {
+    open: BinaryHeap<(Cost, NodeIndexValue), Min, MAX_NODES>,
+    closed: NodeSet,
+    costs: [Cost; MAX_NODES],
+    parents: [NodeIndexValue; MAX_NODES],
+}
+impl Search {
+    fn new(origin: NodeIndexValue) -> Self {
+        let mut search = Self {
+            open: BinaryHeap::new(),
+            closed: NodeSet::new(),
+            costs: [Cost::MAX; MAX_NODES],
+            parents: [-1; MAX_NODES],
+        };
+        search.costs[origin as usize] = 0;
+        // NOTE: the heap is empty
+        search.open.push((0, origin)).unwrap();
+        search
+    }
+    // Cost of the next node to be expanded, or `None` if the search is exhausted
+    fn frontier_cost(&mut self) -> Option<Cost> {
+        while let Some(&(cost, value)) = self.open.peek() {
+            if !self.closed.contains(value) {
+                return Some(cost);
+            }
+            self.open.pop();
+        }
+        None
+    }
+    fn relax(&mut self, value: NodeIndexValue, next: NodeIndexValue, cost: Cost) -> Option<()> {
+        if self.closed.contains(next) || cost >= self.costs[next as usize] {
+            return Some(());
+        }
+        self.costs[next as usize] = cost;
+        self.parents[next as usize] = value;
+        let (closed, costs) = (&self.closed, &self.costs);
+        push_compacting(&mut self.open, (cost, next), |&(c, v)| {
+            !closed.contains(v) && c == costs[v as usize]
+        })
+        .ok()
+    }
+}
+
+/// Same as `dijkstra`, but searches from `start` and `goal` at the same time and stops once the
+/// two searches meet, which usually expands far fewer nodes. The search from `goal` follows the
+/// edges backward, which assumes that every edge can be traversed both ways at the same cost as
+/// on grid mazes; edges failing `graph.edge` in the forward direction are skipped.
+pub fn bidirectional_dijkstra<T: GraphBase>(
+    graph: &T,
+    start: NodeIndex<T>,
+    goal: NodeIndex<T>,
+) -> Option<Route<T>> {
+    let mut forward = Search::new(start.value());
+    let mut backward = Search::new(goal.value());
+    // NOTE: the cheapest route found so far and the node where its two halves meet
+    let mut best = (Cost::MAX, start.value());
+    if start == goal {
+        best.0 = 0;
+    }
+    while let (Some(f), Some(b)) = (forward.frontier_cost(), backward.frontier_cost()) {
+        if f.saturating_add(b) >= best.0 {
+            break;
+        }
+        let is_forward = f <= b;
+        let (search, other) = if is_forward {
+            (&mut forward, &backward)
+        } else {
+            (&mut backward, &forward)
+        };
+        let (cost, value) = search.open.pop()?;
+        search.closed.insert(value);
+        for edge in graph.neighbors(NodeIndex::new(value).ok()?) {
+            let next = edge.to();
+            let edge_cost = if is_forward {
+                edge.cost()
+            } else {
+                match graph.edge(next, edge.from()) {
+                    Some(reverse) => reverse.cost(),
+                    None => continue,
+                }
+            };
+            search.relax(value, next.value(), cost + edge_cost)?;
+            let other_cost = other.costs[next.value() as usize];
+            if other_cost != Cost::MAX {
+                let total = search.costs[next.value() as usize] + other_cost;
+                if total < best.0 {
+                    best = (total, next.value());
+                }
+            }
+        }
+    }
+    let (cost, meeting) = best;
+    if cost == Cost::MAX {
+        return None;
+    }
+    let meeting = NodeIndex::new(meeting).ok()?;
+    let route = reconstruct_route(&forward.parents, start, meeting, cost)?;
+    let mut nodes = Vec::<NodeIndex<T>, MAX_NODES>::from_slice(route.nodes()).ok()?;
+    let mut current = meeting;
+    while current != goal {
+        current = NodeIndex::new(backward.parents[current.value() as usize]).ok()?;
+        nodes.push(current).ok()?;
+    }
+    Some(Route::new(nodes, cost))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::four_way_grid::{index, Graph, MAZE_STR};
+
+    #[test]
+    fn dijkstra_route() {
+        let g = Graph {
+            maze: Maze::load_from_str(MAZE_STR).unwrap(),
+        };
+        let route = dijkstra(&g, index(0, 0), index(3, 2)).unwrap();
+        assert_eq!(route.cost(), 5);
+        assert_eq!(route.nodes().len(), 6);
+        assert_eq!(route.nodes().first(), Some(&index(0, 0)));
+        assert_eq!(route.nodes().last(), Some(&index(3, 2)));
+        for pair in route.nodes().windows(2) {
+            assert!(g.edge(pair[0], pair[1]).is_some());
+        }
+    }
+    #[test]
+    fn dijkstra_start_is_goal() {
+        let g = Graph {
+            maze: Maze::load_from_str(MAZE_STR).unwrap(),
+        };
+        let route = dijkstra(&g, index(1, 1), index(1, 1)).unwrap();
+        assert_eq!(route.cost(), 0);
+        assert_eq!(route.nodes(), &[index(1, 1)]);
+    }
+    #[test]
+    fn dijkstra_unreachable() {
+        let g = Graph {
+            maze: Maze::load_from_str(MAZE_STR).unwrap(),
+        };
+        assert!(dijkstra(&g, index(0, 0), index(0, 2)).is_none());
+    }
+    #[test]
+    fn bidirectional_dijkstra_route() {
+        let g = Graph {
+            maze: Maze::load_from_str(MAZE_STR).unwrap(),
+        };
+        let route = bidirectional_dijkstra(&g, index(0, 0), index(3, 2)).unwrap();
+        assert_eq!(route.cost(), 5);
+        assert_eq!(route.nodes().first(), Some(&index(0, 0)));
+        assert_eq!(route.nodes().last(), Some(&index(3, 2)));
+        for pair in route.nodes().windows(2) {
+            assert!(g.edge(pair[0], pair[1]).is_some());
+        }
+        let route = bidirectional_dijkstra(&g, index(1, 1), index(1, 1)).unwrap();
+        assert_eq!(route.nodes(), &[index(1, 1)]);
+        assert!(bidirectional_dijkstra(&g, index(0, 0), index(0, 2)).is_none());
+    }
+    #[test]
+    fn bidirectional_dijkstra_matches_dijkstra() {
+        let mut rng = rand_pcg::Pcg32::new(0xcafef00dd15ea5e5, 0xa02bdbf7bb3c0a7);
+        let start = CoordXY::new(0, 0).unwrap();
+        let goal = CoordXY::new(WIDTH as u8 / 2, WIDTH as u8 / 2).unwrap();
+        for loops in [0, 10, 50] {
+            let g = Graph {
+                maze: Maze::generate_with_loops(&mut rng, start, goal, loops),
+            };
+            for (from, to) in [
+                (index(0, 0), index(WIDTH as u8 / 2, WIDTH as u8 / 2)),
+                (index(3, 1), index(1, 3)),
+            ] {
+                let expected = dijkstra(&g, from, to).unwrap();
+                let route = bidirectional_dijkstra(&g, from, to).unwrap();
+                assert_eq!(route.cost(), expected.cost());
+                assert_eq!(route.nodes().len() as Cost, route.cost() + 1);
+                assert_eq!(route.nodes().first(), Some(&from));
+                assert_eq!(route.nodes().last(), Some(&to));
+                for pair in route.nodes().windows(2) {
+                    assert!(g.edge(pair[0], pair[1]).is_some());
+                }
+            }
+        }
+    }
+}
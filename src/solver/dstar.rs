@@ -0,0 +1,311 @@
+#![forbid(unsafe_code)]
+
+use core::cell::RefCell;
+use core::marker::PhantomData;
+
+use heapless::binary_heap::{BinaryHeap, Min};
+
+use crate::graph::*;
+use crate::solver::*;
+
+// Priority of a node in the open list, compared lexicographically
+type Key = (Cost, Cost);
+
+fn key<T: GraphBase>(
+    g: &[Cost; MAX_NODES],
+    rhs: &[Cost; MAX_NODES],
+    start: NodeIndex<T>,
+    km: Cost,
+    value: NodeIndexValue,
+) -> Key {
+    let v = value as usize;
+    let cost = g[v].min(rhs[v]);
+    let h = NodeIndex::<T>::new(value).map_or(0, |node| T::optimistic_cost(start, node));
+    (cost.saturating_add(h).saturating_add(km), cost)
+}
+
+/// Incremental planner (D* Lite) for replanning while exploring. The distances to `goal` are kept
+/// between calls, so that after a few walls are discovered `compute_shortest_path` only revisits
+/// the nodes whose distances change instead of searching from scratch.
+///
+/// Edges are assumed to be traversable both ways at the same cost, as on grid mazes.
+pub struct DStarLite<T: GraphBase> {
+    start: NodeIndex<T>,
+    goal: NodeIndex<T>,
+    // NOTE: `g` holds the distances to the goal as of the last expansion of each node and `rhs`
+    // their one-step lookahead; a node needs expanding whenever the two disagree
+    g: [Cost; MAX_NODES],
+    rhs: [Cost; MAX_NODES],
+    open: BinaryHeap<(Cost, Cost, NodeIndexValue), Min, MAX_NODES>,
+    // NOTE: offset added to the keys each time the start moves, instead of reordering the heap
+    km: Cost,
+    changed: Vec<NodeIndexValue, MAX_NODES>,
+    expansions: usize,
+    graph_type: PhantomData<T>,
+}
+impl<T: GraphBase> DStarLite<T> {
+    pub fn new(start: NodeIndex<T>, goal: NodeIndex<T>) -> Self {
+        let mut planner = Self {
+            start,
+            goal,
+            g: [Cost::MAX; MAX_NODES],
+            rhs: [Cost::MAX; MAX_NODES],
+            open: BinaryHeap::new(),
+            km: 0,
+            changed: Vec::new(),
+            expansions: 0,
+            graph_type: PhantomData,
+        };
+        planner.rhs[goal.value() as usize] = 0;
+        planner.push(goal.value());
+        planner
+    }
+    /// Number of nodes expanded so far, as a measure of the work done.
+    #[inline]
+    pub fn expansions(&self) -> usize {
+        self.expansions
+    }
+    /// Cost of the route from the start to the goal as of the last `compute_shortest_path`, or
+    /// `Cost::MAX` if there is none.
+    #[inline]
+    pub fn cost(&self) -> Cost {
+        self.g[self.start.value() as usize]
+    }
+    /// Moves the start, e.g. as the agent follows the route.
+    pub fn set_start(&mut self, start: NodeIndex<T>) {
+        self.km = self
+            .km
+            .saturating_add(T::optimistic_cost(self.start, start));
+        self.start = start;
+    }
+    /// Notifies the planner that the edge between `from` and `to` has changed in the graph, e.g.
+    /// because a wall has been found. The change is taken into account by the next
+    /// `compute_shortest_path`.
+    pub fn update_edge(&mut self, from: NodeIndex<T>, to: NodeIndex<T>) {
+        for value in [from.value(), to.value()] {
+            if !self.changed.contains(&value) {
+                // NOTE: every node is listed at most once
+                self.changed.push(value).unwrap();
+            }
+        }
+    }
+    /// Brings the distances up to date with `graph` so far as the route from the start needs.
+    pub fn compute_shortest_path(&mut self, graph: &T) {
+        while let Some(value) = self.changed.pop() {
+            self.update_node(graph, value);
+        }
+        while let Some(&(k1, k2, value)) = self.open.peek() {
+            let u = value as usize;
+            if self.g[u] == self.rhs[u] {
+                // NOTE: stale entry of a node which has been expanded since
+                self.open.pop();
+                continue;
+            }
+            if (k1, k2) != self.key(value) {
+                self.open.pop();
+                self.push(value);
+                continue;
+            }
+            let s = self.start.value() as usize;
+            if (k1, k2) >= self.key(self.start.value()) && self.g[s] == self.rhs[s] {
+                break;
+            }
+            self.open.pop();
+            self.expansions += 1;
+            let Ok(node) = NodeIndex::<T>::new(value) else {
+                continue;
+            };
+            if self.g[u] > self.rhs[u] {
+                self.g[u] = self.rhs[u];
+            } else {
+                self.g[u] = Cost::MAX;
+                self.update_node(graph, value);
+            }
+            for edge in graph.neighbors(node) {
+                // NOTE: the neighbors are the predecessors as well
+                self.update_node(graph, edge.to().value());
+            }
+        }
+    }
+    /// Returns the route from the start to the goal by following the distances computed so far,
+    /// or `None` if the goal cannot be reached.
+    pub fn route(&self, graph: &T) -> Option<Route<T>> {
+        let mut nodes = Vec::<NodeIndex<T>, MAX_NODES>::new();
+        let mut current = self.start;
+        nodes.push(current).ok()?;
+        while current != self.goal {
+            let (cost, next) = graph
+                .neighbors(current)
+                .into_iter()
+                .map(|edge| {
+                    let g = self.g[edge.to().value() as usize];
+                    (edge.cost().saturating_add(g), edge.to())
+                })
+                .min_by_key(|&(cost, _)| cost)?;
+            if cost == Cost::MAX {
+                return None;
+            }
+            current = next;
+            // NOTE: a route longer than the number of nodes would mean a cycle
+            nodes.push(current).ok()?;
+        }
+        Some(Route::new(nodes, self.cost()))
+    }
+
+    fn key(&self, value: NodeIndexValue) -> Key {
+        key(&self.g, &self.rhs, self.start, self.km, value)
+    }
+    fn push(&mut self, value: NodeIndexValue) {
+        let (k1, k2) = self.key(value);
+        let Self {
+            g,
+            rhs,
+            open,
+            start,
+            km,
+            ..
+        } = self;
+        // NOTE: when compacting, keep a single entry with the current key per node to expand,
+        // leaving out `value` itself which gets its fresh entry
+        let kept = RefCell::new(NodeSet::new());
+        push_compacting(open, (k1, k2, value), |&(k1, k2, v)| {
+            let i = v as usize;
+            let current = v != value
+                && g[i] != rhs[i]
+                && (k1, k2) == key(g, rhs, *start, *km, v)
+                && !kept.borrow().contains(v);
+            if current {
+                kept.borrow_mut().insert(v);
+            }
+            current
+        })
+        .expect("a single entry per node fits in the open list");
+    }
+    // Recomputes the lookahead of the node and queues it if it needs expanding
+    fn update_node(&mut self, graph: &T, value: NodeIndexValue) {
+        let Ok(node) = NodeIndex::<T>::new(value) else {
+            return;
+        };
+        let v = value as usize;
+        if node != self.goal {
+            self.rhs[v] = graph
+                .neighbors(node)
+                .iter()
+                .map(|edge| {
+                    edge.cost()
+                        .saturating_add(self.g[edge.to().value() as usize])
+                })
+                .min()
+                .unwrap_or(Cost::MAX);
+        }
+        if self.g[v] != self.rhs[v] {
+            self.push(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::four_way_grid::{index, Graph};
+    use crate::solver::dijkstra::dijkstra;
+
+    #[test]
+    fn dstar_lite_route() {
+        let g = Graph {
+            maze: Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(5, 0).unwrap()),
+        };
+        let mut planner = DStarLite::new(index(0, 0), index(5, 0));
+        planner.compute_shortest_path(&g);
+        assert_eq!(planner.cost(), 5);
+        let route = planner.route(&g).unwrap();
+        assert_eq!(route.cost(), 5);
+        assert_eq!(route.nodes().len(), 6);
+    }
+    #[test]
+    fn dstar_lite_replan() {
+        let mut g = Graph {
+            maze: Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(5, 0).unwrap()),
+        };
+        let mut planner = DStarLite::new(index(0, 0), index(5, 0));
+        planner.compute_shortest_path(&g);
+        let initial = planner.expansions();
+
+        // The agent moves on and finds a wall in the way
+        planner.set_start(index(1, 0));
+        g.maze
+            .set_cell_state(CoordXY::new(2, 0).unwrap(), Direction::East, true);
+        planner.update_edge(index(2, 0), index(3, 0));
+        planner.compute_shortest_path(&g);
+        let replanning = planner.expansions() - initial;
+
+        let route = planner.route(&g).unwrap();
+        let expected = dijkstra(&g, index(1, 0), index(5, 0)).unwrap();
+        assert_eq!(route.cost(), expected.cost());
+        assert_eq!(route.nodes().first(), Some(&index(1, 0)));
+        assert_eq!(route.nodes().last(), Some(&index(5, 0)));
+        for pair in route.nodes().windows(2) {
+            assert!(g.edge(pair[0], pair[1]).is_some());
+        }
+
+        // Dijkstra would settle at least every node closer to the start than the goal
+        let closer = g
+            .maze
+            .reachable_within(&g, CoordXY::new(1, 0).unwrap(), expected.cost() - 1);
+        assert!(replanning < closer.len());
+        let mut fresh = DStarLite::new(index(1, 0), index(5, 0));
+        fresh.compute_shortest_path(&g);
+        assert_eq!(fresh.cost(), expected.cost());
+    }
+    #[test]
+    fn dstar_lite_no_route() {
+        let mut g = Graph {
+            maze: Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(5, 0).unwrap()),
+        };
+        let mut planner = DStarLite::new(index(0, 0), index(5, 0));
+        planner.compute_shortest_path(&g);
+        for direction in [Direction::North, Direction::East] {
+            g.maze
+                .set_cell_state(CoordXY::new(0, 0).unwrap(), direction, true);
+        }
+        planner.update_edge(index(0, 0), index(0, 1));
+        planner.update_edge(index(0, 0), index(1, 0));
+        planner.compute_shortest_path(&g);
+        assert_eq!(planner.cost(), Cost::MAX);
+        assert!(planner.route(&g).is_none());
+    }
+    #[test]
+    fn dstar_lite_explore() {
+        let mut rng = rand_pcg::Pcg32::new(0xcafef00dd15ea5e5, 0xa02bdbf7bb3c0a7);
+        let (start, goal) = (CoordXY::new(0, 0).unwrap(), CoordXY::new(7, 7).unwrap());
+        let truth = Maze::generate_with_loops(&mut rng, start, goal, 20);
+        // The agent starts out assuming that every wall is open
+        let mut g = Graph {
+            maze: Maze::new(start, goal),
+        };
+        let mut planner = DStarLite::new(index(0, 0), index(7, 7));
+        let mut current = index(0, 0);
+        for _ in 0..MAX_NODES {
+            let location = current.to_agent_state(None).location;
+            for direction in Direction::iter() {
+                let wall = truth.cell(location).state_by_direction(direction);
+                if g.maze.cell(location).state_by_direction(direction) != wall {
+                    g.maze.set_cell_state(location, direction, wall);
+                    let next = (location + direction.into()).unwrap();
+                    planner.update_edge(current, Graph::node_index_by_coord_xy(next).unwrap());
+                }
+            }
+            planner.compute_shortest_path(&g);
+            assert_eq!(
+                planner.cost(),
+                dijkstra(&g, current, index(7, 7)).unwrap().cost()
+            );
+            if current == index(7, 7) {
+                return;
+            }
+            current = planner.route(&g).unwrap().nodes()[1];
+            planner.set_start(current);
+        }
+        panic!("the goal was not reached");
+    }
+}
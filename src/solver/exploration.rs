@@ -0,0 +1,188 @@
+#![forbid(unsafe_code)]
+
+use heapless::binary_heap::{BinaryHeap, Min};
+
+use crate::graph::*;
+use crate::solver::*;
+
+/// Strategy choosing which cell to explore next.
+///
+/// Candidates are the frontier cells, i.e. the unvisited cells the agent can walk to from `from`
+/// through visited cells only, as long as the walls known so far in `maze` allow. Walking costs
+/// are those of `T` between the nodes at the centers of the cells.
+pub trait ExplorationPolicy<T: GraphBase> {
+    /// Returns the node to head for next, or `None` if there is no frontier cell left.
+    fn next_target(&self, maze: &Maze, from: NodeIndex<T>) -> Option<NodeIndex<T>>;
+}
+
+/// Picks the frontier cell closest to the agent.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct NearestFrontier;
+
+/// Picks the frontier cell closest to the goal as the crow flies (in Manhattan distance), and the
+/// one closest to the agent among those.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct TowardGoal;
+
+// Lists the frontier nodes reachable from `from` with the costs of walking there, in the order of
+// the costs
+fn frontier<T: GraphBase>(maze: &Maze, from: NodeIndex<T>) -> Vec<(NodeIndex<T>, Cost), MAX_NODES> {
+    let mut nodes = Vec::<(NodeIndex<T>, Cost), MAX_NODES>::new();
+    let mut open = BinaryHeap::<(Cost, NodeIndexValue), Min, MAX_NODES>::new();
+    let mut closed = NodeSet::new();
+    let mut costs = [Cost::MAX; MAX_NODES];
+    costs[from.value() as usize] = 0;
+    // NOTE: the heap is empty
+    open.push((0, from.value())).unwrap();
+    while let Some((cost, value)) = open.pop() {
+        if closed.contains(value) {
+            continue;
+        }
+        closed.insert(value);
+        let Ok(current) = NodeIndex::<T>::new(value) else {
+            continue;
+        };
+        let location = current.to_agent_state(None).location;
+        if !maze.is_visited(location) {
+            // NOTE: every node is closed at most once
+            nodes.push((current, cost)).unwrap();
+            continue;
+        }
+        for direction in Direction::iter() {
+            let Some(Ok(next)) = maze.step(location, direction).map(node_index_at::<T>) else {
+                continue;
+            };
+            let next_cost = cost + T::cost(current, next);
+            if closed.contains(next.value()) || next_cost >= costs[next.value() as usize] {
+                continue;
+            }
+            costs[next.value() as usize] = next_cost;
+            // NOTE: after compaction every open node has a single entry
+            push_compacting(&mut open, (next_cost, next.value()), |&(c, v)| {
+                !closed.contains(v) && c == costs[v as usize]
+            })
+            .unwrap();
+        }
+    }
+    nodes
+}
+
+impl<T: GraphBase> ExplorationPolicy<T> for NearestFrontier {
+    fn next_target(&self, maze: &Maze, from: NodeIndex<T>) -> Option<NodeIndex<T>> {
+        // NOTE: nodes are found in the order of their costs
+        frontier(maze, from).first().map(|&(node, _)| node)
+    }
+}
+
+impl<T: GraphBase> ExplorationPolicy<T> for TowardGoal {
+    fn next_target(&self, maze: &Maze, from: NodeIndex<T>) -> Option<NodeIndex<T>> {
+        let goal = maze.goal;
+        frontier(maze, from)
+            .into_iter()
+            .min_by_key(|&(node, cost)| {
+                let vector = goal - node.to_agent_state(None).location;
+                (vector.manhattan_len(), cost)
+            })
+            .map(|(node, _)| node)
+    }
+}
+
+/// Explores `maze` from `start` by walking to the targets `policy` picks one after another, until
+/// there is no frontier cell left. `sense` reports the walls around a cell in the order of north,
+/// east, south and west, which are recorded with `Maze::sense` as each cell is reached. Returns
+/// the total cost of the walk, which also ends if `policy` picks a node that is not on the
+/// frontier.
+pub fn explore<T: GraphBase, P: ExplorationPolicy<T>, F: FnMut(CoordXY) -> [bool; 4]>(
+    maze: &mut Maze,
+    policy: &P,
+    start: NodeIndex<T>,
+    mut sense: F,
+) -> Cost {
+    let mut current = start;
+    let mut total = 0;
+    loop {
+        let location = current.to_agent_state(None).location;
+        maze.sense(location, sense(location));
+        maze.set_visited(location, true);
+        let Some(target) = policy.next_target(maze, current) else {
+            return total;
+        };
+        let Some(&(_, cost)) = frontier(maze, current).iter().find(|&&(n, _)| n == target) else {
+            return total;
+        };
+        total += cost;
+        current = target;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::four_way_grid::{index, Graph};
+
+    #[test]
+    fn policies_pick_different_targets() {
+        let mut maze = Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(4, 7).unwrap());
+        for x in 0..5 {
+            maze.set_visited(CoordXY::new(x, 0).unwrap(), true);
+        }
+        assert_eq!(
+            NearestFrontier.next_target(&maze, index::<Graph>(0, 0)),
+            Some(index(0, 1))
+        );
+        assert_eq!(
+            TowardGoal.next_target(&maze, index::<Graph>(0, 0)),
+            Some(index(4, 1))
+        );
+    }
+    #[test]
+    fn policies_respect_walls() {
+        let mut maze = Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(4, 7).unwrap());
+        for x in 0..5 {
+            maze.set_visited(CoordXY::new(x, 0).unwrap(), true);
+            maze.set_cell_state(CoordXY::new(x, 0).unwrap(), Direction::North, true);
+        }
+        // The only way out of the visited row leads east
+        assert_eq!(
+            NearestFrontier.next_target(&maze, index::<Graph>(0, 0)),
+            Some(index(5, 0))
+        );
+        assert_eq!(
+            TowardGoal.next_target(&maze, index::<Graph>(0, 0)),
+            Some(index(5, 0))
+        );
+
+        maze.set_cell_state(CoordXY::new(4, 0).unwrap(), Direction::East, true);
+        assert_eq!(
+            ExplorationPolicy::<Graph>::next_target(&NearestFrontier, &maze, index(0, 0)),
+            None
+        );
+    }
+    #[test]
+    fn explore_visits_every_cell() {
+        let mut rng = rand_pcg::Pcg32::new(0xcafef00dd15ea5e5, 0xa02bdbf7bb3c0a7);
+        let (start, goal) = (CoordXY::new(0, 0).unwrap(), CoordXY::new(2, 2).unwrap());
+        let truth = Maze::generate_with_loops(&mut rng, start, goal, 10);
+        let sense =
+            |coord: CoordXY| Direction::ALL.map(|d| truth.cell(coord).state_by_direction(d));
+        let check = |maze: &Maze, cost: Cost| {
+            // Every cell but the start takes at least one step to reach
+            assert!(cost >= (WIDTH * WIDTH) as Cost - 1);
+            for y in 0..WIDTH as u8 {
+                for x in 0..WIDTH as u8 {
+                    let coord = CoordXY::new(x, y).unwrap();
+                    assert!(maze.is_visited(coord));
+                    assert!(maze.is_explored(coord));
+                    assert_eq!(maze.cell(coord).north(), truth.cell(coord).north());
+                    assert_eq!(maze.cell(coord).east(), truth.cell(coord).east());
+                }
+            }
+        };
+        let mut maze = Maze::new(start, goal);
+        let cost = explore(&mut maze, &NearestFrontier, index::<Graph>(0, 0), sense);
+        check(&maze, cost);
+        let mut maze = Maze::new(start, goal);
+        let cost = explore(&mut maze, &TowardGoal, index::<Graph>(0, 0), sense);
+        check(&maze, cost);
+    }
+}
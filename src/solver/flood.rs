@@ -0,0 +1,174 @@
+#![forbid(unsafe_code)]
+
+use heapless::Deque;
+
+use crate::graph::*;
+use crate::solver::*;
+
+/// Computes the cost from every node to `goal`, expanding breadth-first from the goal. Nodes which
+/// cannot reach the goal are left at `Cost::MAX`.
+pub fn flood_fill<T: GraphBase>(graph: &T, goal: NodeIndex<T>) -> [Cost; MAX_NODES] {
+    flood_fill_until(graph, goal, |_| false)
+}
+
+/// Same as `flood_fill` but never enters the nodes for which `barrier` returns `true`, leaving
+/// them and anything only reachable through them at `Cost::MAX`.
+pub fn flood_fill_until<T: GraphBase, F: Fn(NodeIndex<T>) -> bool>(
+    graph: &T,
+    goal: NodeIndex<T>,
+    barrier: F,
+) -> [Cost; MAX_NODES] {
+    let mut distances = [Cost::MAX; MAX_NODES];
+    let mut queued = [false; MAX_NODES];
+    let mut queue = Deque::<NodeIndexValue, MAX_NODES>::new();
+    distances[goal.value() as usize] = 0;
+    queued[goal.value() as usize] = true;
+    queue.push_back(goal.value()).unwrap();
+    while let Some(value) = queue.pop_front() {
+        queued[value as usize] = false;
+        // NOTE: the queue only holds valid indices
+        let current = NodeIndex::new(value).unwrap();
+        for edge in graph.neighbors(current) {
+            if barrier(edge.to()) {
+                continue;
+            }
+            // NOTE: the cost is measured toward the goal, i.e. along the reversed edge
+            let next = edge.to().value();
+            let distance = distances[value as usize] + T::cost(edge.to(), current);
+            if distance < distances[next as usize] {
+                distances[next as usize] = distance;
+                if !queued[next as usize] {
+                    queued[next as usize] = true;
+                    // NOTE: every node is queued at most once at a time
+                    queue.push_back(next).unwrap();
+                }
+            }
+        }
+    }
+    distances
+}
+
+/// Returns the neighbor of `from` with the lowest value in `distances` as computed by
+/// `flood_fill`, or `None` if no neighbor is closer to the goal than `from` itself (e.g. at the
+/// goal).
+pub fn next_step_down_gradient<T: GraphBase>(
+    graph: &T,
+    from: NodeIndex<T>,
+    distances: &[Cost; MAX_NODES],
+) -> Option<NodeIndex<T>> {
+    graph
+        .neighbors(from)
+        .iter()
+        .map(|edge| edge.to())
+        .filter(|to| distances[to.value() as usize] < distances[from.value() as usize])
+        .min_by_key(|to| distances[to.value() as usize])
+}
+
+/// Writes the distances of a four-way grid, indexed by `x + y * WIDTH`, as a CSV grid of `WIDTH`
+/// rows from the top (`y = WIDTH - 1`) down like the maze is drawn. Unreachable cells are left
+/// empty.
+#[cfg(feature = "std")]
+pub fn distances_to_csv(dist: &[Cost]) -> std::string::String {
+    use std::fmt::Write;
+    let mut csv = std::string::String::new();
+    for y in (0..WIDTH).rev() {
+        for x in 0..WIDTH {
+            if x > 0 {
+                csv.push(',');
+            }
+            match dist.get(x + y * WIDTH) {
+                Some(&distance) if distance != Cost::MAX => {
+                    // NOTE: writing to a String never fails
+                    write!(csv, "{distance}").unwrap();
+                }
+                _ => {}
+            }
+        }
+        csv.push('\n');
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::four_way_grid::{index, Graph, MAZE_STR};
+
+    #[test]
+    fn flood_fill_distances() {
+        let g = Graph {
+            maze: Maze::load_from_str(MAZE_STR).unwrap(),
+        };
+        let distances = flood_fill(&g, index(3, 2));
+        assert_eq!(distances[index::<Graph>(3, 2).value() as usize], 0);
+        assert_eq!(distances[index::<Graph>(2, 2).value() as usize], 1);
+        assert_eq!(distances[index::<Graph>(0, 0).value() as usize], 5);
+        assert_eq!(distances[index::<Graph>(0, 2).value() as usize], Cost::MAX);
+    }
+    #[test]
+    fn flood_fill_descent() {
+        let g = Graph {
+            maze: Maze::load_from_str(MAZE_STR).unwrap(),
+        };
+        let goal = index(3, 2);
+        let distances = flood_fill(&g, goal);
+        let mut current = index(0, 0);
+        let mut route = Vec::<NodeIndex<Graph>, MAX_NODES>::new();
+        route.push(current).unwrap();
+        while let Some(next) = next_step_down_gradient(&g, current, &distances) {
+            // Strictly decreasing by the edge cost
+            assert_eq!(
+                distances[next.value() as usize] + 1,
+                distances[current.value() as usize]
+            );
+            current = next;
+            route.push(current).unwrap();
+        }
+        assert_eq!(current, goal);
+        let shortest = crate::solver::astar::astar(&g, index(0, 0), goal).unwrap();
+        assert_eq!(route.len(), shortest.nodes().len());
+        for node in shortest.nodes().windows(2) {
+            assert!(distances[node[1].value() as usize] < distances[node[0].value() as usize]);
+        }
+        // Nowhere to go from an unreachable cell
+        assert_eq!(next_step_down_gradient(&g, index(0, 2), &distances), None);
+    }
+    #[test]
+    fn flood_fill_until_barrier() {
+        let g = Graph {
+            maze: Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(1, 1).unwrap()),
+        };
+        // Column 2 splits the maze into two sides
+        let distances = flood_fill_until(&g, index(1, 1), |node| {
+            node.to_agent_state(None).location.x().value() == 2
+        });
+        for y in 0..WIDTH as u8 {
+            for x in 0..WIDTH as u8 {
+                let distance = distances[index::<Graph>(x, y).value() as usize];
+                if x < 2 {
+                    assert_eq!(distance, x.abs_diff(1) as Cost + y.abs_diff(1) as Cost);
+                } else {
+                    assert_eq!(distance, Cost::MAX);
+                }
+            }
+        }
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn flood_fill_distances_to_csv() {
+        let g = Graph {
+            maze: Maze::load_from_str(MAZE_STR).unwrap(),
+        };
+        let csv = distances_to_csv(&flood_fill(&g, index(3, 2)));
+        let rows: std::vec::Vec<std::vec::Vec<&str>> =
+            csv.lines().map(|l| l.split(',').collect()).collect();
+        assert_eq!(rows.len(), WIDTH);
+        assert!(rows.iter().all(|r| r.len() == WIDTH));
+        // The bottom row comes last
+        let row = |y: usize| &rows[WIDTH - 1 - y];
+        assert_eq!(row(2)[3], "0");
+        assert_eq!(row(2)[2], "1");
+        assert_eq!(row(0)[0], "5");
+        assert_eq!(row(2)[0], "");
+    }
+}
@@ -0,0 +1,59 @@
+#![forbid(unsafe_code)]
+
+use crate::graph::*;
+use crate::solver::astar::{astar, astar_filtered};
+
+/// Returns the shortest route from `start` to `goal` which differs from the optimal one in at
+/// least one edge, or `None` if there is no such route.
+pub fn second_shortest<T: GraphBase>(
+    graph: &T,
+    start: NodeIndex<T>,
+    goal: NodeIndex<T>,
+) -> Option<Route<T>> {
+    let shortest = astar(graph, start, goal)?;
+    let mut second: Option<Route<T>> = None;
+    // NOTE: any other simple route lacks at least one edge of the shortest one
+    for pair in shortest.nodes().windows(2) {
+        let (from, to) = (pair[0], pair[1]);
+        let route = astar_filtered(graph, start, goal, |edge| {
+            edge.from() != from || edge.to() != to
+        });
+        if let Some(route) = route {
+            if second.as_ref().is_none_or(|s| route.cost() < s.cost()) {
+                second = Some(route);
+            }
+        }
+    }
+    second
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::four_way_grid::{index, Graph, MAZE_STR};
+    use crate::types::*;
+
+    #[test]
+    fn second_shortest_takes_the_loop() {
+        let g = Graph {
+            maze: Maze::load_from_str(MAZE_STR).unwrap(),
+        };
+        let shortest = astar(&g, index(1, 0), index(2, 0)).unwrap();
+        assert_eq!(shortest.cost(), 1);
+        let second = second_shortest(&g, index(1, 0), index(2, 0)).unwrap();
+        assert_eq!(second.cost(), 3);
+        assert_ne!(second, shortest);
+        assert_eq!(second.nodes().first(), Some(&index(1, 0)));
+        assert_eq!(second.nodes().last(), Some(&index(2, 0)));
+        assert!(second.nodes().contains(&index(1, 1)));
+    }
+    #[test]
+    fn second_shortest_single_route() {
+        let g = Graph {
+            maze: Maze::load_from_str(MAZE_STR).unwrap(),
+        };
+        // (0, 0) and (0, 1) are dead ends, each reached through a single corridor
+        assert!(second_shortest(&g, index(0, 0), index(1, 0)).is_none());
+        assert!(second_shortest(&g, index(0, 1), index(0, 2)).is_none());
+    }
+}
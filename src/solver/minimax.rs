@@ -0,0 +1,106 @@
+#![forbid(unsafe_code)]
+
+use heapless::binary_heap::{BinaryHeap, Min};
+
+use crate::graph::*;
+use crate::solver::*;
+
+/// Finds the route from `start` to `goal` whose most expensive edge is as cheap as possible, e.g.
+/// to stay away from a dangerous cell even at the price of a longer route. Ties are broken by the
+/// total cost, which is also the cost of the returned route. Fails with `Error::NoRoute` if the
+/// goal cannot be reached.
+pub fn minimax_route<T: GraphBase>(
+    graph: &T,
+    start: NodeIndex<T>,
+    goal: NodeIndex<T>,
+) -> Result<Route<T>, Error> {
+    // NOTE: nodes are ordered by their bottleneck first and their total cost second
+    let mut open = BinaryHeap::<(Cost, Cost, NodeIndexValue), Min, MAX_NODES>::new();
+    let mut closed = NodeSet::new();
+    let mut labels = [(Cost::MAX, Cost::MAX); MAX_NODES];
+    let mut parents = [-1 as NodeIndexValue; MAX_NODES];
+
+    labels[start.value() as usize] = (0, 0);
+    open.push((0, 0, start.value()))
+        .map_err(|_| Error::OutOfRange)?;
+    while let Some((_, _, value)) = open.pop() {
+        if closed.contains(value) {
+            continue;
+        }
+        let current = NodeIndex::new(value)?;
+        if current == goal {
+            let (_, cost) = labels[value as usize];
+            return reconstruct_route(&parents, start, goal, cost).ok_or(Error::NoRoute);
+        }
+        closed.insert(value);
+        let (bottleneck, sum) = labels[value as usize];
+        for edge in graph.neighbors(current) {
+            let next = edge.to().value();
+            let label = (bottleneck.max(edge.cost()), sum + edge.cost());
+            if closed.contains(next) || label >= labels[next as usize] {
+                continue;
+            }
+            labels[next as usize] = label;
+            parents[next as usize] = value;
+            push_compacting(&mut open, (label.0, label.1, next), |&(b, s, v)| {
+                !closed.contains(v) && (b, s) == labels[v as usize]
+            })?;
+        }
+    }
+    Err(Error::NoRoute)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::four_way_grid::{index, Graph};
+    use crate::solver::astar::astar;
+
+    // Four-way grid where entering (1, 0) costs 2
+    struct Hazard;
+    impl CostScaling for Hazard {
+        fn cost(_from: NodeIndex<Graph>, to: NodeIndex<Graph>, base: Cost) -> Cost {
+            if to == index(1, 0) {
+                2 * base
+            } else {
+                base
+            }
+        }
+    }
+
+    #[test]
+    fn minimax_route_avoids_hazard() {
+        let g = ScaledGrid::<Hazard>::new(Graph {
+            maze: Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(2, 0).unwrap()),
+        });
+        // The shortest route passes through the hazard
+        let route = astar(&g, index(0, 0), index(2, 0)).unwrap();
+        assert_eq!(route.cost(), 3);
+        assert_eq!(
+            route.nodes(),
+            [(0, 0), (1, 0), (2, 0)].map(|(x, y)| index(x, y))
+        );
+
+        // Going around it is longer but never takes an edge costing more than 1
+        let route = minimax_route(&g, index(0, 0), index(2, 0)).unwrap();
+        assert_eq!(route.cost(), 4);
+        assert_eq!(
+            route.nodes(),
+            [(0, 0), (0, 1), (1, 1), (2, 1), (2, 0)].map(|(x, y)| index(x, y))
+        );
+    }
+    #[test]
+    fn minimax_route_no_route() {
+        let mut maze = Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(2, 0).unwrap());
+        for direction in [Direction::North, Direction::East] {
+            maze.set_cell_state(CoordXY::new(0, 0).unwrap(), direction, true);
+        }
+        let g = Graph { maze };
+        assert_eq!(
+            minimax_route(&g, index(0, 0), index(2, 0)).err(),
+            Some(Error::NoRoute)
+        );
+        let route = minimax_route(&g, index(0, 0), index(0, 0)).unwrap();
+        assert_eq!(route.cost(), 0);
+    }
+}
@@ -0,0 +1,120 @@
+#![forbid(unsafe_code)]
+
+use heapless::binary_heap::{BinaryHeap, Min};
+
+use crate::graph::*;
+use crate::types::*;
+
+pub mod astar;
+pub mod dijkstra;
+pub mod dstar;
+pub mod exploration;
+pub mod flood;
+pub mod k_shortest;
+pub mod minimax;
+pub mod optimistic;
+pub mod wall_follow;
+
+// TODO: use MAX_NODE_INDEX (once generic_const_exprs gets stabilized)
+pub(crate) const MAX_NODES: usize = WIDTH * WIDTH;
+
+#[derive(Debug, Clone)]
+pub(crate) struct NodeSet {
+    bits: [u32; MAX_NODES.div_ceil(32)],
+}
+impl NodeSet {
+    #[inline]
+    pub(crate) fn new() -> Self {
+        Self {
+            bits: [0; MAX_NODES.div_ceil(32)],
+        }
+    }
+    #[inline]
+    pub(crate) fn contains(&self, value: NodeIndexValue) -> bool {
+        let value = value as usize;
+        self.bits[value / 32] & (1 << (value % 32)) != 0
+    }
+    #[inline]
+    pub(crate) fn insert(&mut self, value: NodeIndexValue) {
+        let value = value as usize;
+        self.bits[value / 32] |= 1 << (value % 32);
+    }
+}
+
+// Pushes `entry` onto `open`. When the heap is full, the stale entries, i.e. those `is_current`
+// rejects, are dropped first so that every open node has at most one entry.
+pub(crate) fn push_compacting<E: Ord, const N: usize>(
+    open: &mut BinaryHeap<E, Min, N>,
+    entry: E,
+    is_current: impl Fn(&E) -> bool,
+) -> Result<(), Error> {
+    if open.len() == open.capacity() {
+        for e in core::mem::take(open).into_vec() {
+            if is_current(&e) {
+                // NOTE: fewer entries than before are put back
+                open.push(e).ok();
+            }
+        }
+    }
+    open.push(entry).map_err(|_| Error::OutOfRange)
+}
+
+// Walks `parents` back from `goal` and builds the route in the forward order
+pub(crate) fn reconstruct_route<T: GraphBase>(
+    parents: &[NodeIndexValue; MAX_NODES],
+    start: NodeIndex<T>,
+    goal: NodeIndex<T>,
+    cost: Cost,
+) -> Option<Route<T>> {
+    let mut nodes = Vec::<NodeIndex<T>, MAX_NODES>::new();
+    let mut current = goal;
+    nodes.push(current).ok()?;
+    while current != start {
+        current = NodeIndex::new(parents[current.value() as usize]).ok()?;
+        nodes.push(current).ok()?;
+    }
+    nodes.reverse();
+    Some(Route::new(nodes, cost))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn node_set() {
+        let mut set = NodeSet::new();
+        assert!(!set.contains(0));
+        set.insert(0);
+        set.insert(33);
+        assert!(set.contains(0));
+        assert!(set.contains(33));
+        assert!(!set.contains(1));
+        assert!(!set.contains(32));
+    }
+    #[test]
+    fn push_compacting_drops_stale_entries() {
+        let mut open = BinaryHeap::<(Cost, NodeIndexValue), Min, 4>::new();
+        for entry in [(3, 0), (1, 1), (2, 0), (4, 2)] {
+            push_compacting(&mut open, entry, |_| true).unwrap();
+        }
+        // Only the latest entry of node 0 is current
+        let is_current = |&(c, v): &(Cost, NodeIndexValue)| v != 0 || c == 2;
+        push_compacting(&mut open, (5, 3), is_current).unwrap();
+        assert_eq!(open.len(), 4);
+        assert_eq!(
+            open.into_vec()
+                .into_iter()
+                .map(|(_, v)| v)
+                .sum::<NodeIndexValue>(),
+            6
+        );
+
+        let mut full = BinaryHeap::<(Cost, NodeIndexValue), Min, 1>::new();
+        push_compacting(&mut full, (0, 0), |_| true).unwrap();
+        assert_eq!(
+            push_compacting(&mut full, (1, 1), |_| true),
+            Err(Error::OutOfRange)
+        );
+    }
+}
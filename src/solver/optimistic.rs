@@ -0,0 +1,83 @@
+#![forbid(unsafe_code)]
+
+use crate::graph::exploration_grid::ExplorationGrid;
+use crate::graph::*;
+use crate::solver::astar::astar;
+use crate::types::*;
+
+/// Finds the best-case route from `start` to `goal`, assuming that every wall whose check bit is
+/// not set in `maze` is open. Fails with `Error::NoRoute` if even that does not reach the goal.
+pub fn optimistic_route<T: GraphBase>(
+    maze: &Maze,
+    graph: &T,
+    start: NodeIndex<T>,
+    goal: NodeIndex<T>,
+) -> Result<Route<T>, Error> {
+    let optimistic = ExplorationGrid::new(maze, graph);
+    let route =
+        astar(&optimistic, start.transmute_to(), goal.transmute_to()).ok_or(Error::NoRoute)?;
+    let nodes = route
+        .nodes()
+        .iter()
+        .map(|&node| node.transmute_to())
+        .collect();
+    Ok(Route::new(nodes, route.cost()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::four_way_grid::{index, Graph};
+
+    #[test]
+    fn optimistic_route_through_unexplored() {
+        // Walls everywhere except for a detour around a wall east of (1, 0)
+        let mut maze = Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(3, 0).unwrap());
+        for y in 0..WIDTH as u8 {
+            for x in 0..WIDTH as u8 {
+                let coord = CoordXY::new(x, y).unwrap();
+                maze.set_cell_state(coord, Direction::North, true);
+                maze.set_cell_state(coord, Direction::East, true);
+            }
+        }
+        for (x, y, direction) in [
+            (0, 0, Direction::East),
+            (1, 0, Direction::North),
+            (1, 1, Direction::East),
+            (2, 1, Direction::South),
+            (2, 0, Direction::East),
+        ] {
+            maze.set_cell_state(CoordXY::new(x, y).unwrap(), direction, false);
+        }
+        let graph = Graph { maze };
+        assert_eq!(astar(&graph, index(0, 0), index(3, 0)).unwrap().cost(), 5);
+
+        // Nothing is checked yet, so the wall east of (1, 0) might be open
+        let route = optimistic_route(&graph.maze, &graph, index(0, 0), index(3, 0)).unwrap();
+        assert_eq!(route.cost(), 3);
+        assert_eq!(
+            route.nodes(),
+            &[index(0, 0), index(1, 0), index(2, 0), index(3, 0)]
+        );
+
+        // Once the wall is sensed the detour is the best case
+        let mut maze = graph.maze;
+        maze.set_cell_check(CoordXY::new(1, 0).unwrap(), Direction::East, true);
+        let graph = Graph { maze };
+        let route = optimistic_route(&graph.maze, &graph, index(0, 0), index(3, 0)).unwrap();
+        assert_eq!(route.cost(), 5);
+    }
+    #[test]
+    fn optimistic_route_no_route() {
+        let mut maze = Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(3, 0).unwrap());
+        for direction in [Direction::North, Direction::East] {
+            maze.set_cell_state(CoordXY::new(0, 0).unwrap(), direction, true);
+            maze.set_cell_check(CoordXY::new(0, 0).unwrap(), direction, true);
+        }
+        let graph = Graph { maze };
+        assert_eq!(
+            optimistic_route(&graph.maze, &graph, index(0, 0), index(3, 0)).err(),
+            Some(Error::NoRoute)
+        );
+    }
+}
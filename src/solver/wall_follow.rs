@@ -0,0 +1,105 @@
+#![forbid(unsafe_code)]
+
+use crate::graph::four_way_grid::Graph;
+use crate::types::*;
+
+// Moves to the neighbor in the first direction of `turns` without a wall
+fn follow_wall(graph: &Graph, state: &AgentState, turns: [Turn; 4]) -> Option<AgentState> {
+    let cell = graph.maze.cell(state.location);
+    for turn in turns {
+        let heading_vector = state.apply_turn(turn).heading_vector;
+        let direction = Direction::try_from(heading_vector).ok()?;
+        if cell.state_by_direction(direction) {
+            continue;
+        }
+        if let Ok(location) = state.location + heading_vector {
+            return Some(AgentState {
+                location,
+                heading_vector,
+                ..*state
+            });
+        }
+    }
+    None
+}
+
+/// Takes one step of the left-hand rule: turns left, goes straight, turns right or goes back,
+/// whichever comes first without a wall. Returns `None` if the heading of `state` is not
+/// orthogonal or the cell is closed on all sides.
+pub fn left_hand_step(graph: &Graph, state: &AgentState) -> Option<AgentState> {
+    use Turn::*;
+    follow_wall(graph, state, [Left, Straight, Right, Back])
+}
+
+/// Takes one step of the right-hand rule, the mirror of `left_hand_step`.
+pub fn right_hand_step(graph: &Graph, state: &AgentState) -> Option<AgentState> {
+    use Turn::*;
+    follow_wall(graph, state, [Right, Straight, Left, Back])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::GOAL_MAZE_STR;
+
+    fn graph() -> Graph {
+        let mut maze = Maze::load_from_str(GOAL_MAZE_STR).unwrap();
+        // NOTE: close the rest of the maze so that the followers stay in the loaded part
+        for y in 0..4 {
+            maze.set_cell_state(CoordXY::new(3, y).unwrap(), Direction::East, true);
+        }
+        for x in 0..4 {
+            maze.set_cell_state(CoordXY::new(x, 3).unwrap(), Direction::North, true);
+        }
+        Graph { maze }
+    }
+
+    fn walk(graph: &Graph, step: fn(&Graph, &AgentState) -> Option<AgentState>) -> Option<usize> {
+        let mut state = AgentState {
+            location: graph.maze.start,
+            local_location: CellLocalLocation::Center,
+            heading_vector: Direction::North.into(),
+        };
+        for steps in 0..4 * WIDTH * WIDTH {
+            if graph.maze.is_goal(state.location) {
+                return Some(steps);
+            }
+            state = step(graph, &state)?;
+        }
+        None
+    }
+
+    #[test]
+    fn left_hand_step_turns() {
+        let g = graph();
+        let start = AgentState {
+            location: CoordXY::new(0, 0).unwrap(),
+            local_location: CellLocalLocation::Center,
+            heading_vector: Direction::North.into(),
+        };
+        // The wall on the left leaves the way north into a dead end
+        let next = left_hand_step(&g, &start).unwrap();
+        assert_eq!(next.location, CoordXY::new(0, 1).unwrap());
+        assert_eq!(next.heading_vector, Direction::North.into());
+        // Turns back at the end of it
+        let next = left_hand_step(&g, &next).unwrap();
+        assert_eq!(next.location, CoordXY::new(0, 0).unwrap());
+        assert_eq!(next.heading_vector, Direction::South.into());
+        // The right-hand follower turns east instead
+        let next = right_hand_step(&g, &start).unwrap();
+        assert_eq!(next.location, CoordXY::new(1, 0).unwrap());
+        assert_eq!(next.heading_vector, Direction::East.into());
+
+        let stopped = AgentState {
+            heading_vector: VectorXY { x: 0, y: 0 },
+            ..start
+        };
+        assert_eq!(left_hand_step(&g, &stopped), None);
+    }
+    #[test]
+    fn wall_followers_reach_goal() {
+        let g = graph();
+        assert!(walk(&g, left_hand_step).is_some());
+        assert!(walk(&g, right_hand_step).is_some());
+    }
+}
@@ -0,0 +1,121 @@
+#![forbid(unsafe_code)]
+
+use core::fmt::Write;
+use std::string::String;
+
+use crate::graph::*;
+use crate::types::*;
+
+// Size of a cell in SVG user units
+const CELL_SIZE: usize = 20;
+
+// Maps a lattice point of the maze to SVG coordinates (y axis pointing down)
+#[inline]
+fn point(x: usize, y: usize) -> (usize, usize) {
+    (x * CELL_SIZE, (WIDTH - y) * CELL_SIZE)
+}
+
+impl Maze {
+    /// Draws the maze as an SVG image with one `<line>` per wall, a marker for the start and the
+    /// goal, and `route` as a polyline through the cell centers if given.
+    pub fn to_svg<T: GraphBase>(&self, route: Option<&Route<T>>) -> String {
+        let size = WIDTH * CELL_SIZE;
+        let mut svg = String::new();
+        // NOTE: writing to a String never fails
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{size}" height="{size}" viewBox="0 0 {size} {size}">"#
+        )
+        .unwrap();
+        writeln!(svg, r#"<g stroke="black" stroke-width="2">"#).unwrap();
+        for y in 0..WIDTH {
+            for x in 0..WIDTH {
+                let cell = self.data[x + y * WIDTH];
+                let mut walls = Vec::<((usize, usize), (usize, usize)), 4>::new();
+                if cell.north() {
+                    walls.push(((x, y + 1), (x + 1, y + 1))).unwrap();
+                }
+                if cell.east() {
+                    walls.push(((x + 1, y), (x + 1, y + 1))).unwrap();
+                }
+                // NOTE: south and west walls are drawn by the neighbors except on the perimeter
+                if y == 0 && cell.south() {
+                    walls.push(((x, y), (x + 1, y))).unwrap();
+                }
+                if x == 0 && cell.west() {
+                    walls.push(((x, y), (x, y + 1))).unwrap();
+                }
+                for (from, to) in walls {
+                    let (x1, y1) = point(from.0, from.1);
+                    let (x2, y2) = point(to.0, to.1);
+                    writeln!(svg, r#"<line x1="{x1}" y1="{y1}" x2="{x2}" y2="{y2}"/>"#).unwrap();
+                }
+            }
+        }
+        writeln!(svg, "</g>").unwrap();
+        for (coord, color) in [(self.start, "green"), (self.goal, "red")] {
+            let (x, y) = point(coord.x().value() as usize, coord.y().value() as usize + 1);
+            writeln!(
+                svg,
+                r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{color}"/>"#,
+                x + CELL_SIZE / 4,
+                y + CELL_SIZE / 4,
+                CELL_SIZE / 2,
+                CELL_SIZE / 2
+            )
+            .unwrap();
+        }
+        if let Some(route) = route {
+            write!(svg, r#"<polyline fill="none" stroke="blue" points=""#).unwrap();
+            for (i, node) in route.nodes().iter().enumerate() {
+                let location = node.to_agent_state(None).location;
+                let (x, y) = point(
+                    location.x().value() as usize,
+                    location.y().value() as usize + 1,
+                );
+                let separator = if i == 0 { "" } else { " " };
+                write!(
+                    svg,
+                    "{separator}{},{}",
+                    x + CELL_SIZE / 2,
+                    y + CELL_SIZE / 2
+                )
+                .unwrap();
+            }
+            writeln!(svg, r#""/>"#).unwrap();
+        }
+        writeln!(svg, "</svg>").unwrap();
+        svg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::four_way_grid::Graph;
+
+    #[test]
+    fn to_svg_walls() {
+        let mut maze = Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(2, 2).unwrap());
+        let svg = maze.to_svg::<Graph>(None);
+        assert_eq!(svg.matches("<line").count(), 4 * WIDTH);
+        assert_eq!(svg.matches("<rect").count(), 2);
+        assert!(!svg.contains("<polyline"));
+
+        maze.set_cell_state(CoordXY::new(1, 1).unwrap(), Direction::North, true);
+        maze.set_cell_state(CoordXY::new(1, 1).unwrap(), Direction::West, true);
+        let svg = maze.to_svg::<Graph>(None);
+        assert_eq!(svg.matches("<line").count(), 4 * WIDTH + 2);
+    }
+    #[test]
+    fn to_svg_route() {
+        let maze = Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(1, 0).unwrap());
+        let mut nodes = Vec::new();
+        nodes.push(NodeIndex::new(0).unwrap()).unwrap();
+        nodes.push(NodeIndex::new(1).unwrap()).unwrap();
+        let route = Route::<Graph>::new(nodes, 1);
+        let svg = maze.to_svg(Some(&route));
+        let bottom = (WIDTH - 1) * CELL_SIZE + CELL_SIZE / 2;
+        assert!(svg.contains(&std::format!(r#"points="10,{bottom} 30,{bottom}""#)));
+    }
+}
@@ -1,8 +1,12 @@
 #![forbid(unsafe_code)]
+// NOTE: the code generated by `#[bitfield]` contains redundant parentheses
+#![allow(unused_parens)]
 
-use core::{fmt, ops::Add, ops::Sub};
+use core::{fmt, ops::Add, ops::Neg, ops::Sub};
 pub use heapless::Vec;
 use modular_bitfield::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "maze_8x8")] {
@@ -23,10 +27,45 @@ pub enum Error {
     InvalidLocation,
     InvalidDirection,
     InvalidVector,
+    GenerationFailed,
+    NoRoute,
+    ParseError,
+    IoError,
+    /// The wall in the given direction from the cell is set on one side only, or is missing on
+    /// the outer boundary.
+    InconsistentWall(CoordXY, Direction),
 }
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::OutOfRange => write!(f, "value out of range"),
+            Error::InvalidLocation => write!(f, "invalid location"),
+            Error::InvalidDirection => write!(f, "invalid direction"),
+            Error::InvalidVector => write!(f, "invalid vector"),
+            Error::GenerationFailed => write!(f, "maze generation failed"),
+            Error::NoRoute => write!(f, "no route found"),
+            Error::ParseError => write!(f, "malformed input"),
+            Error::IoError => write!(f, "I/O error"),
+            Error::InconsistentWall(coord, direction) => write!(
+                f,
+                "inconsistent wall {:?} of ({}, {})",
+                direction, coord.x.value, coord.y.value
+            ),
+        }
+    }
+}
+impl From<core::num::TryFromIntError> for Error {
+    #[inline]
+    fn from(_: core::num::TryFromIntError) -> Self {
+        Error::OutOfRange
+    }
+}
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
 
 #[non_exhaustive]
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Direction {
     North,
     East,
@@ -34,6 +73,38 @@ pub enum Direction {
     West,
 }
 impl Direction {
+    /// Every direction, clockwise from north.
+    pub const ALL: [Direction; 4] = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ];
+    /// Iterates over `ALL`.
+    #[inline]
+    pub fn iter() -> impl Iterator<Item = Direction> {
+        Self::ALL.into_iter()
+    }
+    #[inline]
+    pub fn rotated_cw(&self) -> Self {
+        use Direction::*;
+        match *self {
+            North => East,
+            East => South,
+            South => West,
+            West => North,
+        }
+    }
+    #[inline]
+    pub fn rotated_ccw(&self) -> Self {
+        use Direction::*;
+        match *self {
+            North => West,
+            East => North,
+            South => East,
+            West => South,
+        }
+    }
     #[inline]
     pub fn inverted(&self) -> Self {
         use Direction::*;
@@ -61,6 +132,7 @@ impl TryFrom<VectorXY> for Direction {
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Coord1D {
     value: u8,
 }
@@ -90,6 +162,7 @@ impl Coord1D {
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct CoordXY {
     x: Coord1D,
     y: Coord1D,
@@ -111,6 +184,57 @@ impl CoordXY {
     pub fn y(&self) -> Coord1D {
         self.y
     }
+    /// Lists the orthogonally adjacent cells inside the grid in the order of north, east, south
+    /// and west.
+    pub fn adjacent(&self) -> Vec<CoordXY, 4> {
+        use Direction::*;
+        [North, East, South, West]
+            .into_iter()
+            .filter_map(|direction| (*self + direction.into()).ok())
+            .collect()
+    }
+    /// Returns the vertical and the horizontal direction leading from `self` to `other` if they
+    /// touch at a corner, e.g. `(North, East)` when `other` is to the northeast.
+    pub fn is_diagonal_neighbor(&self, other: &CoordXY) -> Option<(Direction, Direction)> {
+        use Direction::*;
+        match *other - *self {
+            VectorXY { x: 1, y: 1 } => Some((North, East)),
+            VectorXY { x: -1, y: 1 } => Some((North, West)),
+            VectorXY { x: 1, y: -1 } => Some((South, East)),
+            VectorXY { x: -1, y: -1 } => Some((South, West)),
+            _ => None,
+        }
+    }
+    /// Returns the cell moved along with the whole grid rotated clockwise by 90 degrees.
+    #[inline]
+    pub fn rotated_cw(&self) -> CoordXY {
+        Self {
+            x: self.y,
+            y: Coord1D {
+                value: Coord1D::MAX - self.x.value,
+            },
+        }
+    }
+    /// Returns the cell moved along with the whole grid rotated counterclockwise by 90 degrees.
+    #[inline]
+    pub fn rotated_ccw(&self) -> CoordXY {
+        Self {
+            x: Coord1D {
+                value: Coord1D::MAX - self.y.value,
+            },
+            y: self.x,
+        }
+    }
+    /// Returns the cell moved along with the whole grid flipped left to right.
+    #[inline]
+    pub fn mirrored_x(&self) -> CoordXY {
+        Self {
+            x: Coord1D {
+                value: Coord1D::MAX - self.x.value,
+            },
+            y: self.y,
+        }
+    }
 }
 impl Add<VectorXY> for CoordXY {
     type Output = Result<CoordXY, Error>;
@@ -139,6 +263,7 @@ impl Sub<CoordXY> for CoordXY {
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct VectorXY {
     pub x: i8,
     pub y: i8,
@@ -155,6 +280,58 @@ impl From<Direction> for VectorXY {
         }
     }
 }
+// NOTE: the arithmetic on vectors saturates at the range of i8 instead of overflowing
+impl Add for VectorXY {
+    type Output = VectorXY;
+    #[inline]
+    fn add(self, rhs: VectorXY) -> Self::Output {
+        VectorXY {
+            x: self.x.saturating_add(rhs.x),
+            y: self.y.saturating_add(rhs.y),
+        }
+    }
+}
+impl Sub for VectorXY {
+    type Output = VectorXY;
+    #[inline]
+    fn sub(self, rhs: VectorXY) -> Self::Output {
+        VectorXY {
+            x: self.x.saturating_sub(rhs.x),
+            y: self.y.saturating_sub(rhs.y),
+        }
+    }
+}
+impl Neg for VectorXY {
+    type Output = VectorXY;
+    #[inline]
+    fn neg(self) -> Self::Output {
+        VectorXY {
+            x: self.x.saturating_neg(),
+            y: self.y.saturating_neg(),
+        }
+    }
+}
+impl VectorXY {
+    #[inline]
+    pub fn scaled(self, factor: i8) -> VectorXY {
+        VectorXY {
+            x: self.x.saturating_mul(factor),
+            y: self.y.saturating_mul(factor),
+        }
+    }
+    #[inline]
+    pub fn manhattan_len(self) -> u16 {
+        self.x.unsigned_abs() as u16 + self.y.unsigned_abs() as u16
+    }
+    /// Rotates the vector clockwise by 90 degrees, e.g. from north to east.
+    #[inline]
+    pub fn rotated_cw(self) -> VectorXY {
+        VectorXY {
+            x: self.y,
+            y: self.x.saturating_neg(),
+        }
+    }
+}
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum CellLocalLocation {
@@ -164,6 +341,17 @@ pub enum CellLocalLocation {
     South,
     West,
 }
+impl From<Direction> for CellLocalLocation {
+    #[inline]
+    fn from(value: Direction) -> Self {
+        match value {
+            Direction::North => CellLocalLocation::North,
+            Direction::East => CellLocalLocation::East,
+            Direction::South => CellLocalLocation::South,
+            Direction::West => CellLocalLocation::West,
+        }
+    }
+}
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct AgentState {
@@ -171,6 +359,139 @@ pub struct AgentState {
     pub local_location: CellLocalLocation,
     pub heading_vector: VectorXY,
 }
+impl AgentState {
+    /// Orders states by their location in row-major order, i.e. by `(y, x)`, ignoring the local
+    /// location and the heading.
+    #[inline]
+    pub fn cmp_position(&self, other: &AgentState) -> core::cmp::Ordering {
+        (self.location.y.value, self.location.x.value)
+            .cmp(&(other.location.y.value, other.location.x.value))
+    }
+    /// Returns `true` if the agent is in the cell at `coord`, whichever way it is heading.
+    #[inline]
+    pub fn is_at(&self, coord: CoordXY) -> bool {
+        self.location == coord
+    }
+    /// Rotates the heading by `turn` in steps of 90 degrees, counterclockwise for `Turn::Left`.
+    /// A zero heading vector stays zero.
+    pub fn apply_turn(&self, turn: Turn) -> AgentState {
+        let VectorXY { x, y } = self.heading_vector;
+        let heading_vector = match turn {
+            Turn::Straight => VectorXY { x, y },
+            Turn::Left => VectorXY { x: -y, y: x },
+            Turn::Right => VectorXY { x: y, y: -x },
+            Turn::Back => VectorXY { x: -x, y: -y },
+        };
+        AgentState {
+            heading_vector,
+            ..*self
+        }
+    }
+    #[inline]
+    pub fn turn_left(&mut self) {
+        *self = self.apply_turn(Turn::Left);
+    }
+    #[inline]
+    pub fn turn_right(&mut self) {
+        *self = self.apply_turn(Turn::Right);
+    }
+    #[inline]
+    pub fn turn_around(&mut self) {
+        *self = self.apply_turn(Turn::Back);
+    }
+    /// Moves the agent by its heading vector, keeping the heading and the local location. Fails
+    /// with `Error::OutOfRange` if that leaves the grid.
+    pub fn step_forward(&self) -> Result<AgentState, Error> {
+        Ok(AgentState {
+            location: (self.location + self.heading_vector)?,
+            ..*self
+        })
+    }
+}
+
+pub const MAX_COMMANDS: usize = 2 * WIDTH * WIDTH;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Turn {
+    Straight,
+    Left,
+    Right,
+    Back,
+}
+impl Turn {
+    /// Classifies the change of heading from `from` to `to` by the sign of their cross product.
+    pub fn between(from: VectorXY, to: VectorXY) -> Self {
+        let cross = from.x as i16 * to.y as i16 - from.y as i16 * to.x as i16;
+        let dot = from.x as i16 * to.x as i16 + from.y as i16 * to.y as i16;
+        match cross {
+            0 if dot < 0 => Turn::Back,
+            0 => Turn::Straight,
+            c if c > 0 => Turn::Left,
+            _ => Turn::Right,
+        }
+    }
+    #[inline]
+    pub fn inverted(&self) -> Self {
+        use Turn::*;
+        match *self {
+            Straight => Straight,
+            Left => Right,
+            Right => Left,
+            Back => Back,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Command {
+    Forward(u8),
+    Turn(Turn),
+}
+impl Command {
+    #[inline]
+    pub fn inverted(&self) -> Self {
+        match *self {
+            Command::Forward(cells) => Command::Forward(cells),
+            Command::Turn(turn) => Command::Turn(turn.inverted()),
+        }
+    }
+}
+
+/// Returns the commands that retrace `commands` backward: the robot turns around, replays the
+/// inverted commands in reverse order and turns once more to face `final_heading`, given that it
+/// was heading `start_heading` when it started executing `commands`. Fails with
+/// `Error::OutOfRange` if `commands` has more than `MAX_COMMANDS` elements.
+pub fn invert_commands(
+    commands: &[Command],
+    start_heading: Direction,
+    final_heading: Direction,
+) -> Result<Vec<Command, { MAX_COMMANDS + 2 }>, Error> {
+    if commands.len() > MAX_COMMANDS {
+        return Err(Error::OutOfRange);
+    }
+    let mut inverted = Vec::<Command, { MAX_COMMANDS + 2 }>::new();
+    // NOTE: there is room for the commands and the two turns
+    inverted.push(Command::Turn(Turn::Back)).unwrap();
+    for command in commands.iter().rev() {
+        inverted.push(command.inverted()).unwrap();
+    }
+    // NOTE: the robot arrives at the start heading opposite to `start_heading`
+    match Turn::between(start_heading.inverted().into(), final_heading.into()) {
+        Turn::Straight => {}
+        turn => inverted.push(Command::Turn(turn)).unwrap(),
+    }
+    Ok(inverted)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum JunctionType {
+    Closed,
+    DeadEnd,
+    Corridor,
+    Corner,
+    ThreeWay,
+    FourWay,
+}
 
 #[bitfield]
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -184,6 +505,24 @@ pub struct Cell {
     pub check_south: bool,
     pub check_west: bool,
 }
+impl Default for Cell {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+// NOTE: `#[bitfield]` hides the fields, so a cell goes over the wire as its byte
+#[cfg(feature = "serde")]
+impl Serialize for Cell {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(self.into_bytes()[0])
+    }
+}
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Cell {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        u8::deserialize(deserializer).map(|byte| Self::from_bytes([byte]))
+    }
+}
 impl Cell {
     pub fn state_by_direction(&self, direction: Direction) -> bool {
         use Direction::*;
@@ -221,14 +560,128 @@ impl Cell {
             West => self.set_check_west(value),
         }
     }
+    pub fn open_directions(&self) -> Vec<Direction, 4> {
+        let mut directions = Vec::<Direction, 4>::new();
+        for direction in [
+            Direction::North,
+            Direction::East,
+            Direction::South,
+            Direction::West,
+        ] {
+            if !self.state_by_direction(direction) {
+                directions.push(direction).unwrap();
+            }
+        }
+        directions
+    }
+    /// Number of sides without a wall.
+    #[inline]
+    pub fn open_count(&self) -> u8 {
+        4 - (self.into_bytes()[0] & 0x0f).count_ones() as u8
+    }
+    /// Compares the four walls only, ignoring the check bits.
+    #[inline]
+    pub fn walls_eq(&self, other: &Cell) -> bool {
+        (self.into_bytes()[0] ^ other.into_bytes()[0]) & 0x0f == 0
+    }
+}
+
+// NOTE: box-drawing characters are accepted as well as ASCII
+#[inline]
+fn is_horizontal_wall(c: char) -> bool {
+    matches!(c, '-' | '─' | '━' | '═')
+}
+#[inline]
+fn is_vertical_wall(c: char) -> bool {
+    matches!(c, '|' | '│' | '┃' | '║')
+}
+
+// Base-2 logarithm of `v` with 8 fractional bits, for `v > 0`
+fn log2_x256(v: u32) -> u32 {
+    let integer = v.ilog2();
+    // NOTE: `x` is `v` normalized into [1, 2) with 16 fractional bits
+    let mut x = ((v as u64) << 16) >> integer;
+    let mut result = integer << 8;
+    for bit in (0..8).rev() {
+        x = (x * x) >> 16;
+        if x >= 2 << 16 {
+            x >>= 1;
+            result |= 1 << bit;
+        }
+    }
+    result
+}
+
+pub const CHECKS_BYTES: usize = WIDTH * WIDTH / 2 + WIDTH * WIDTH / 8;
+// NOTE: each plane holds the WIDTH * (WIDTH - 1) interior walls of one orientation
+const WALL_PLANE_BITS: usize = WIDTH * (WIDTH - 1);
+/// Length of the encoding `Maze::to_wall_bytes` produces.
+pub const WALL_BYTES: usize = 5 + (2 * WALL_PLANE_BITS).div_ceil(8);
+/// Length of the string `Maze::to_str` produces.
+pub const MAZE_STR_LEN: usize = (4 * WIDTH + 2) * (2 * WIDTH + 1);
+
+// Serializes the arrays of `Maze`, which are too long for the built-in impls of serde
+#[cfg(feature = "serde")]
+mod serde_array {
+    use core::{fmt, marker::PhantomData};
+    use serde::de::{Error, SeqAccess, Visitor};
+    use serde::ser::SerializeTuple;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer, T: Serialize, const N: usize>(
+        array: &[T; N],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut tuple = serializer.serialize_tuple(N)?;
+        for element in array {
+            tuple.serialize_element(element)?;
+        }
+        tuple.end()
+    }
+
+    struct ArrayVisitor<T, const N: usize>(PhantomData<T>);
+    impl<'de, T: Deserialize<'de> + Copy + Default, const N: usize> Visitor<'de>
+        for ArrayVisitor<T, N>
+    {
+        type Value = [T; N];
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "an array of length {}", N)
+        }
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut array = [T::default(); N];
+            for (i, element) in array.iter_mut().enumerate() {
+                *element = seq
+                    .next_element()?
+                    .ok_or_else(|| A::Error::invalid_length(i, &self))?;
+            }
+            Ok(array)
+        }
+    }
+
+    pub fn deserialize<
+        'de,
+        D: Deserializer<'de>,
+        T: Deserialize<'de> + Copy + Default,
+        const N: usize,
+    >(
+        deserializer: D,
+    ) -> Result<[T; N], D::Error> {
+        deserializer.deserialize_tuple(N, ArrayVisitor(PhantomData))
+    }
 }
 
 #[non_exhaustive]
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Maze {
     pub start: CoordXY,
     pub goal: CoordXY,
+    #[cfg_attr(feature = "serde", serde(with = "serde_array"))]
     pub data: [Cell; WIDTH * WIDTH],
+    #[cfg_attr(feature = "serde", serde(with = "serde_array"))]
+    visited: [u8; WIDTH * WIDTH / 8],
+    // NOTE: the goal region is the square of this size whose lower-left cell is `goal`
+    goal_size: u8,
 }
 impl Maze {
     pub fn new(start: CoordXY, goal: CoordXY) -> Self {
@@ -241,55 +694,206 @@ impl Maze {
             data[y * WIDTH].set_west(true);
             data[WIDTH - 1 + y * WIDTH].set_east(true);
         }
-        Self { start, goal, data }
-    }
-    pub fn load_from_str(maze_str: &str) -> Self {
-        let mut maze = Self::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(7, 7).unwrap());
-        let mut width = 0;
-        // TODO: Support arbitrary size
-        for w in [32, 16, 9, 8, 4] {
-            let nominal_len = (4 * w + 2) * (2 * w + 1);
-            if maze_str.len() / nominal_len == 1 {
-                width = w;
-                break;
-            }
+        Self {
+            start,
+            goal,
+            data,
+            visited: [0; WIDTH * WIDTH / 8],
+            goal_size: 1,
         }
-        if (width > WIDTH) || (width == 0) {
-            panic!("Loaded data has invalid size {}", width);
+    }
+    /// Same as `new`, but fails with `Error::InvalidLocation` if `start` is in the goal.
+    pub fn new_checked(start: CoordXY, goal: CoordXY) -> Result<Self, Error> {
+        let maze = Self::new(start, goal);
+        if maze.is_goal(start) {
+            return Err(Error::InvalidLocation);
         }
+        Ok(maze)
+    }
+    /// Parses a maze drawn in ASCII or box-drawing characters, with the cells marked as in
+    /// `Display`. Fails with `Error::ParseError` if the width cannot be told from the length, a
+    /// line is too short or the goal region is not a square.
+    pub fn load_from_str(maze_str: &str) -> Result<Self, Error> {
+        let mut maze = Self::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(7, 7).unwrap());
+        let mut goal_region_marks = 0;
+        // NOTE: a maze of width w has 2w + 1 lines of 4w + 1 characters and a line break each
+        let len = maze_str.chars().count();
+        let width = (1..=WIDTH)
+            .find(|w| (4 * w + 2) * (2 * w + 1) == len)
+            .ok_or(Error::ParseError)?;
         let mut coord = CoordXY::new(0, (width - 1) as u8).unwrap();
         for (line_no, s) in maze_str.split('\n').enumerate() {
-            coord.y = Coord1D::new((width - 1 - line_no / 2) as u8).unwrap();
+            let char_at = |i: usize| s.chars().nth(i).ok_or(Error::ParseError);
+            coord.y = Coord1D::new((width - 1 - line_no / 2) as u8)?;
             if line_no % 2 == 0 {
                 // Check for walls in north or south
                 for x in 0..width {
-                    coord.x = Coord1D::new(x as u8).unwrap();
-                    if s.as_bytes()[2 + 4 * x] == b'-' {
+                    coord.x = Coord1D::new(x as u8)?;
+                    if is_horizontal_wall(char_at(2 + 4 * x)?) {
                         maze.set_cell_state(coord, Direction::North, true);
                     }
                 }
             } else {
                 // Check for walls in west or east
                 for x in 0..width {
-                    coord.x = Coord1D::new(x as u8).unwrap();
-                    if s.as_bytes()[4 * x] == b'|' {
+                    coord.x = Coord1D::new(x as u8)?;
+                    if is_vertical_wall(char_at(4 * x)?) {
                         maze.set_cell_state(coord, Direction::West, true);
                     }
-                    if s.as_bytes()[4 * x + 2] == b'S' {
+                    if char_at(4 * x + 2)? == 'S' {
                         maze.start = coord;
-                    } else if s.as_bytes()[4 * x + 2] == b'G' {
+                    } else if char_at(4 * x + 2)? == 'G' {
                         maze.goal = coord;
+                    } else if char_at(4 * x + 2)? == 'g' {
+                        goal_region_marks += 1;
                     }
-                    if s.as_bytes()[4 * x + 4] == b'|' {
+                    if is_vertical_wall(char_at(4 * x + 4)?) {
                         maze.set_cell_state(coord, Direction::East, true);
                     }
                 }
                 if coord.y.value == 0 {
-                    break;
+                    let size = (1..=WIDTH as u8)
+                        .find(|&size| size as usize * size as usize == goal_region_marks + 1)
+                        .ok_or(Error::ParseError)?;
+                    maze.set_goal_size(size).map_err(|_| Error::ParseError)?;
+                    return Ok(maze);
                 }
             }
         }
-        maze
+        Err(Error::ParseError)
+    }
+    #[cfg(feature = "std")]
+    pub fn load_from_reader<R: std::io::Read>(mut reader: R) -> Result<Self, Error> {
+        let mut maze_str = std::string::String::new();
+        reader
+            .read_to_string(&mut maze_str)
+            .map_err(|_| Error::IoError)?;
+        Self::load_from_str(&maze_str)
+    }
+    /// Writes the maze in the format read by `load_from_str`, with the cells marked as in
+    /// `Display`. Loading the result gives back the same walls as long as the outer boundary of
+    /// the maze is closed.
+    pub fn to_str(&self) -> heapless::String<MAZE_STR_LEN> {
+        use core::fmt::Write;
+        let mut maze_str = heapless::String::<MAZE_STR_LEN>::new();
+        // NOTE: the rendered maze is exactly MAZE_STR_LEN long
+        write!(maze_str, "{}", self.marks()).unwrap();
+        maze_str
+    }
+    /// Parses the CSV format with a `width,height` header followed by one `x,y,n,e,s,w` row per
+    /// cell, where the last four fields are 1 for a wall and 0 otherwise. Every cell must appear
+    /// exactly once and the walls shared by neighboring cells must agree.
+    pub fn from_csv(csv: &str) -> Result<Self, Error> {
+        let mut maze = Self::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(7, 7).unwrap());
+        let mut lines = csv.lines().map(str::trim).filter(|l| !l.is_empty());
+        let fields = |line: &str| -> Result<Vec<u8, 6>, Error> {
+            let mut values = Vec::<u8, 6>::new();
+            for field in line.split(',') {
+                let value = field.trim().parse().map_err(|_| Error::ParseError)?;
+                values.push(value).map_err(|_| Error::ParseError)?;
+            }
+            Ok(values)
+        };
+        if fields(lines.next().ok_or(Error::ParseError)?)? != [WIDTH as u8, WIDTH as u8] {
+            return Err(Error::ParseError);
+        }
+        let mut seen = [false; WIDTH * WIDTH];
+        for line in lines {
+            let values = fields(line)?;
+            let [x, y, n, e, s, w] = values[..] else {
+                return Err(Error::ParseError);
+            };
+            let coord = CoordXY::new(x, y).map_err(|_| Error::ParseError)?;
+            let i = x as usize + y as usize * WIDTH;
+            if [n, e, s, w].iter().any(|&wall| wall > 1) || seen[i] {
+                return Err(Error::ParseError);
+            }
+            seen[i] = true;
+            *maze.mutable_cell(coord) = Cell::new()
+                .with_north(n == 1)
+                .with_east(e == 1)
+                .with_south(s == 1)
+                .with_west(w == 1);
+        }
+        if seen.contains(&false) {
+            return Err(Error::ParseError);
+        }
+        for y in 0..WIDTH {
+            for x in 0..WIDTH {
+                let cell = maze.data[x + y * WIDTH];
+                if (y + 1 < WIDTH && cell.north() != maze.data[x + (y + 1) * WIDTH].south())
+                    || (x + 1 < WIDTH && cell.east() != maze.data[x + 1 + y * WIDTH].west())
+                {
+                    return Err(Error::ParseError);
+                }
+            }
+        }
+        Ok(maze)
+    }
+    #[inline]
+    pub fn is_start(&self, coord: CoordXY) -> bool {
+        self.start == coord
+    }
+    /// Returns `true` if `coord` is in the goal region.
+    #[inline]
+    pub fn is_goal(&self, coord: CoordXY) -> bool {
+        (self.goal.x.value..self.goal.x.value + self.goal_size).contains(&coord.x.value)
+            && (self.goal.y.value..self.goal.y.value + self.goal_size).contains(&coord.y.value)
+    }
+    /// Makes the goal region a `size` by `size` square with `goal` as its lower-left cell, e.g. 2
+    /// for the 2x2 goal of a classic micromouse maze. Fails with `Error::OutOfRange` if `size` is
+    /// 0 or the region does not fit in the maze.
+    pub fn set_goal_size(&mut self, size: u8) -> Result<(), Error> {
+        let fits = |v: u8| v as usize + size as usize <= WIDTH;
+        if size == 0 || !fits(self.goal.x.value) || !fits(self.goal.y.value) {
+            return Err(Error::OutOfRange);
+        }
+        self.goal_size = size;
+        Ok(())
+    }
+    /// Iterates over the cells of the goal region in row-major order.
+    pub fn goal_cells(&self) -> impl Iterator<Item = CoordXY> {
+        let (goal, size) = (self.goal, self.goal_size);
+        (0..size).flat_map(move |dy| {
+            (0..size).filter_map(move |dx| CoordXY::new(goal.x.value + dx, goal.y.value + dy).ok())
+        })
+    }
+    /// Returns `true` if the agent is back in the start cell.
+    #[inline]
+    pub fn is_home(&self, state: &AgentState) -> bool {
+        state.is_at(self.start)
+    }
+    pub fn junction_type(&self, coord: CoordXY) -> JunctionType {
+        let open = self.cell(coord).open_directions();
+        match open.len() {
+            0 => JunctionType::Closed,
+            1 => JunctionType::DeadEnd,
+            2 if open[0].inverted() == open[1] => JunctionType::Corridor,
+            2 => JunctionType::Corner,
+            3 => JunctionType::ThreeWay,
+            _ => JunctionType::FourWay,
+        }
+    }
+    /// Bits 0 to 3 are set when north, east, south and west are open, respectively.
+    #[inline]
+    pub fn openness_mask(&self, coord: CoordXY) -> u8 {
+        !self.cell(coord).into_bytes()[0] & 0x0f
+    }
+    /// Shannon entropy of the distribution of openness masks over all cells, in bits scaled by
+    /// 256. A maze made of a single kind of cell scores 0, and the score grows as the kinds of
+    /// cells get more varied, up to 1024 when all 16 masks are equally common.
+    pub fn wall_entropy(&self) -> u32 {
+        let mut counts = [0u32; 16];
+        for cell in self.data.iter() {
+            counts[(!cell.into_bytes()[0] & 0x0f) as usize] += 1;
+        }
+        let total = (WIDTH * WIDTH) as u32;
+        let sum: u64 = counts
+            .iter()
+            .filter(|&&count| count > 0)
+            .map(|&count| count as u64 * (log2_x256(total) - log2_x256(count)) as u64)
+            .sum();
+        (sum / total as u64) as u32
     }
     #[inline]
     pub fn cell_by_x_y(&self, x: Coord1D, y: Coord1D) -> Cell {
@@ -317,49 +921,588 @@ impl Maze {
                 .set_state_by_direction(direction.inverted(), state);
         }
     }
-    pub fn set_cell_check(&mut self, coord: CoordXY, direction: Direction, state: bool) {
-        self.mutable_cell(coord)
-            .set_check_by_direction(direction, state);
-        if let Ok(next_coord) = coord + direction.into() {
-            self.mutable_cell(next_coord)
-                .set_check_by_direction(direction.inverted(), state);
+    /// Lists the directions without a wall around the cell at `coord`, in the order of north,
+    /// east, south and west.
+    #[inline]
+    pub fn open_directions(&self, coord: CoordXY) -> Vec<Direction, 4> {
+        self.cell(coord).open_directions()
+    }
+    /// Number of walls around the cell at `coord`, e.g. 3 for a dead end.
+    #[inline]
+    pub fn wall_count(&self, coord: CoordXY) -> u8 {
+        4 - self.cell(coord).open_count()
+    }
+    /// Returns the cell next to `from` in `direction`, or `None` if there is a wall in between.
+    pub fn step(&self, from: CoordXY, direction: Direction) -> Option<CoordXY> {
+        if self.cell(from).state_by_direction(direction) {
+            return None;
         }
+        (from + direction.into()).ok()
     }
-}
-impl fmt::Display for Maze {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for y in (0..WIDTH).rev() {
-            for x in 0..WIDTH {
+    /// Lists the cells seen from `from` looking along `heading`, nearest first, up to the first
+    /// wall. The cell at `from` itself is not included.
+    pub fn line_of_sight(&self, from: CoordXY, heading: Direction) -> Vec<CoordXY, WIDTH> {
+        let mut cells = Vec::<CoordXY, WIDTH>::new();
+        let mut current = from;
+        while let Some(next) = self.step(current, heading) {
+            // NOTE: a straight line crosses fewer than `WIDTH` other cells
+            cells.push(next).unwrap();
+            current = next;
+        }
+        cells
+    }
+    /// Lists the cells which can be reached from `from` through open walls, nearest first.
+    pub fn reachable_cells(&self, from: CoordXY) -> Vec<CoordXY, { WIDTH * WIDTH }> {
+        let mut cells = Vec::<CoordXY, { WIDTH * WIDTH }>::new();
+        let mut visited = [0u8; WIDTH * WIDTH / 8];
+        let mut visit = |coord: CoordXY| {
+            let i = coord.x.value as usize + coord.y.value as usize * WIDTH;
+            let first = visited[i / 8] & (1 << (i % 8)) == 0;
+            visited[i / 8] |= 1 << (i % 8);
+            first
+        };
+        let mut queue = heapless::Deque::<CoordXY, { WIDTH * WIDTH }>::new();
+        visit(from);
+        queue.push_back(from).unwrap();
+        while let Some(current) = queue.pop_front() {
+            // NOTE: every cell is queued at most once
+            cells.push(current).unwrap();
+            use Direction::*;
+            for direction in [North, East, South, West] {
+                if let Some(next) = self.step(current, direction) {
+                    if visit(next) {
+                        queue.push_back(next).unwrap();
+                    }
+                }
+            }
+        }
+        cells
+    }
+    /// Returns `true` if `to` can be reached from `from` through open walls.
+    pub fn is_reachable(&self, from: CoordXY, to: CoordXY) -> bool {
+        self.reachable_cells(from).contains(&to)
+    }
+    /// Walls off every dead end other than the start and the goal region, repeatedly, until none is
+    /// left. What stays open are the routes between the start and the goal together with any
+    /// loops, which makes searching them cheaper.
+    pub fn fill_dead_ends(&mut self) {
+        let is_dead_end = |maze: &Maze, coord| {
+            maze.wall_count(coord) == 3 && !maze.is_start(coord) && !maze.is_goal(coord)
+        };
+        let mut stack = Vec::<CoordXY, { WIDTH * WIDTH }>::new();
+        for i in 0..WIDTH * WIDTH {
+            let coord = CoordXY::new((i % WIDTH) as u8, (i / WIDTH) as u8).unwrap();
+            if is_dead_end(self, coord) {
+                // NOTE: every cell is pushed at most once, as walls are only ever added
+                stack.push(coord).unwrap();
+            }
+        }
+        while let Some(coord) = stack.pop() {
+            let Some(&direction) = self.open_directions(coord).first() else {
+                continue;
+            };
+            self.set_cell_state(coord, direction, true);
+            if let Ok(next) = coord + direction.into() {
+                if is_dead_end(self, next) {
+                    stack.push(next).unwrap();
+                }
+            }
+        }
+    }
+    /// Removes the walls and clears the checks between the cells of the `size` by `size` square
+    /// whose lower-left cell is `origin`. The walls around the square and everything outside of it
+    /// are left untouched. Fails with `Error::OutOfRange` if the square does not fit in the maze.
+    pub fn reset_region(&mut self, origin: CoordXY, size: usize) -> Result<(), Error> {
+        let (x0, y0) = (origin.x.value as usize, origin.y.value as usize);
+        if x0 + size > WIDTH || y0 + size > WIDTH {
+            return Err(Error::OutOfRange);
+        }
+        for y in y0..y0 + size {
+            for x in x0..x0 + size {
+                let coord = CoordXY::new(x as u8, y as u8)?;
+                // NOTE: each inner wall is cleared from its south-west side
+                if x + 1 < x0 + size {
+                    self.set_cell_state(coord, Direction::East, false);
+                    self.set_cell_check(coord, Direction::East, false);
+                }
+                if y + 1 < y0 + size {
+                    self.set_cell_state(coord, Direction::North, false);
+                    self.set_cell_check(coord, Direction::North, false);
+                }
+            }
+        }
+        Ok(())
+    }
+    /// Doubles the lower-left `WIDTH / 2` by `WIDTH / 2` part of the maze so that it covers the
+    /// whole grid. Each cell becomes an open 2x2 block surrounded by the walls of the original
+    /// cell, so that blocks are connected exactly when the original cells are. The start and the
+    /// goal are moved to the lower-left cells of their blocks, or to the last row or column if
+    /// they lie outside of the scaled part.
+    pub fn upscale(&self) -> Maze {
+        let scale = |coord: CoordXY| {
+            let scale_1d = |v: u8| (2 * v as usize).min(WIDTH - 1) as u8;
+            CoordXY::new(scale_1d(coord.x.value), scale_1d(coord.y.value)).unwrap()
+        };
+        let mut maze = Maze::new(scale(self.start), scale(self.goal));
+        for y in 0..WIDTH / 2 {
+            for x in 0..WIDTH / 2 {
                 let cell = self.data[x + y * WIDTH];
-                write!(f, "+{}", if cell.north() { "---" } else { "   " }).unwrap();
+                for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                    let coord = CoordXY::new((2 * x + dx) as u8, (2 * y + dy) as u8).unwrap();
+                    use Direction::*;
+                    for (direction, outer) in [
+                        (North, dy == 1),
+                        (East, dx == 1),
+                        (South, dy == 0),
+                        (West, dx == 0),
+                    ] {
+                        if outer && cell.state_by_direction(direction) {
+                            maze.set_cell_state(coord, direction, true);
+                        }
+                    }
+                }
+            }
+        }
+        maze
+    }
+    /// Returns the maze rotated clockwise by 90 degrees, e.g. to view it from a robot starting
+    /// in another corner. Walls, checks, visited cells, the start and the goal region are all
+    /// rotated together.
+    pub fn rotated_cw(&self) -> Maze {
+        // NOTE: the goal region is rotated around, so its lower-left cell comes from its
+        // lower-right one
+        let goal_offset = VectorXY {
+            x: self.goal_size as i8 - 1,
+            y: 0,
+        };
+        let goal = (self.goal + goal_offset).unwrap_or(self.goal);
+        let mut maze = Maze::new(self.start.rotated_cw(), goal.rotated_cw());
+        maze.goal_size = self.goal_size;
+        for i in 0..WIDTH * WIDTH {
+            let coord = CoordXY::new((i % WIDTH) as u8, (i / WIDTH) as u8).unwrap();
+            let (cell, rotated) = (self.data[i], coord.rotated_cw());
+            for direction in Direction::iter() {
+                let target = maze.mutable_cell(rotated);
+                target.set_state_by_direction(
+                    direction.rotated_cw(),
+                    cell.state_by_direction(direction),
+                );
+                target.set_check_by_direction(
+                    direction.rotated_cw(),
+                    cell.check_by_direction(direction),
+                );
+            }
+            maze.set_visited(rotated, self.is_visited(coord));
+        }
+        maze
+    }
+    /// Overwrites the walls and checks of the cell at `coord`, updating the neighbors so that
+    /// shared walls stay consistent.
+    pub fn set_cell(&mut self, coord: CoordXY, cell: Cell) {
+        use Direction::*;
+        for direction in [North, East, South, West] {
+            self.set_cell_state(coord, direction, cell.state_by_direction(direction));
+            self.set_cell_check(coord, direction, cell.check_by_direction(direction));
+        }
+    }
+    /// Sets the walls listed in `updates`, each of which gives the state of the wall on the side
+    /// `direction` of the cell at `coord`. Returns the cells on either side of the walls which
+    /// actually changed, each listed once in the order they were first touched; updates leaving a
+    /// wall as it was add nothing. The set holds at most every cell of the maze, so it cannot run
+    /// out of capacity.
+    pub fn apply_updates(
+        &mut self,
+        updates: &[(CoordXY, Direction, bool)],
+    ) -> Vec<CoordXY, { WIDTH * WIDTH }> {
+        let mut affected = Vec::<CoordXY, { WIDTH * WIDTH }>::new();
+        for &(coord, direction, state) in updates {
+            if self.cell(coord).state_by_direction(direction) == state {
+                continue;
+            }
+            self.set_cell_state(coord, direction, state);
+            let next_coord = coord + direction.into();
+            for c in [Ok(coord), next_coord].into_iter().flatten() {
+                if !affected.contains(&c) {
+                    // NOTE: `affected` can hold every cell in the maze, so this never fails
+                    affected.push(c).unwrap();
+                }
             }
-            writeln!(f, "+").unwrap();
+        }
+        affected
+    }
+    /// Counts the interior walls whose state differs between `self` and `other`. Each shared
+    /// wall is counted once.
+    pub fn wall_hamming(&self, other: &Maze) -> u32 {
+        let mut distance = 0;
+        for y in 0..WIDTH {
             for x in 0..WIDTH {
-                let cell = self.data[x + y * WIDTH];
-                let coord = CoordXY::new(x as u8, y as u8).unwrap();
-                let mut cell_mark = " ";
-                if self.start == coord {
-                    cell_mark = "S";
-                } else if self.goal == coord {
-                    cell_mark = "G";
+                let (a, b) = (self.data[x + y * WIDTH], other.data[x + y * WIDTH]);
+                if y < WIDTH - 1 && a.north() != b.north() {
+                    distance += 1;
+                }
+                if x < WIDTH - 1 && a.east() != b.east() {
+                    distance += 1;
                 }
-                write!(f, "{} {} ", if cell.west() { "|" } else { " " }, cell_mark).unwrap();
             }
-            writeln!(f, "|").unwrap();
         }
-        for _ in 0..WIDTH {
-            write!(f, "+---").unwrap();
+        distance
+    }
+    /// Returns a maze with walls exactly where the walls of `self` and `other` differ, e.g. to
+    /// highlight the walls found or lost between two runs. The outer boundary is compared the
+    /// same way, so it is open wherever both mazes agree. Start and goal are taken from `self`
+    /// and no check bits are set.
+    pub fn xor_walls(&self, other: &Maze) -> Maze {
+        let mut maze = Self::new(self.start, self.goal);
+        maze.goal_size = self.goal_size;
+        for (cell, (a, b)) in maze.data.iter_mut().zip(self.data.iter().zip(&other.data)) {
+            *cell = Cell::from_bytes([(a.into_bytes()[0] ^ b.into_bytes()[0]) & 0x0f]);
+        }
+        maze
+    }
+    /// Yields every interior wall segment once, as the east and north walls of each cell in
+    /// row-major order, together with whether the wall is present.
+    pub fn wall_segments(&self) -> impl Iterator<Item = (CoordXY, Direction, bool)> + '_ {
+        (0..WIDTH * WIDTH).flat_map(move |i| {
+            let coord = CoordXY::new((i % WIDTH) as u8, (i / WIDTH) as u8).unwrap();
+            let cell = self.data[i];
+            let east =
+                (coord.x.value < Coord1D::MAX).then_some((coord, Direction::East, cell.east()));
+            let north =
+                (coord.y.value < Coord1D::MAX).then_some((coord, Direction::North, cell.north()));
+            east.into_iter().chain(north)
+        })
+    }
+    /// Checks that every wall shared by two cells is set on both sides or neither, and that the
+    /// outer boundary is closed. Worth calling after building `data` by hand.
+    pub fn validate(&self) -> Result<(), Error> {
+        for i in 0..WIDTH * WIDTH {
+            let coord = CoordXY::new((i % WIDTH) as u8, (i / WIDTH) as u8)?;
+            use Direction::*;
+            for direction in [North, East, South, West] {
+                let wall = self.data[i].state_by_direction(direction);
+                let consistent = match coord + direction.into() {
+                    Ok(next) => wall == self.cell(next).state_by_direction(direction.inverted()),
+                    Err(_) => wall,
+                };
+                if !consistent {
+                    return Err(Error::InconsistentWall(coord, direction));
+                }
+            }
         }
-        writeln!(f, "+").unwrap();
         Ok(())
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    const MAZE_STR: &str = "\
+    #[inline]
+    pub fn is_visited(&self, coord: CoordXY) -> bool {
+        let i = coord.x.value as usize + coord.y.value as usize * WIDTH;
+        self.visited[i / 8] & (1 << (i % 8)) != 0
+    }
+    #[inline]
+    pub fn set_visited(&mut self, coord: CoordXY, state: bool) {
+        let i = coord.x.value as usize + coord.y.value as usize * WIDTH;
+        if state {
+            self.visited[i / 8] |= 1 << (i % 8);
+        } else {
+            self.visited[i / 8] &= !(1 << (i % 8));
+        }
+    }
+    /// Returns the average location of the visited cells (rounded to the nearest cell), or
+    /// `None` if no cell has been visited.
+    pub fn explored_centroid(&self) -> Option<CoordXY> {
+        let (mut sum_x, mut sum_y, mut count) = (0usize, 0usize, 0usize);
+        for y in 0..WIDTH {
+            for x in 0..WIDTH {
+                let coord = CoordXY::new(x as u8, y as u8).unwrap();
+                if self.is_visited(coord) {
+                    sum_x += x;
+                    sum_y += y;
+                    count += 1;
+                }
+            }
+        }
+        if count == 0 {
+            return None;
+        }
+        CoordXY::new(
+            ((sum_x + count / 2) / count) as u8,
+            ((sum_y + count / 2) / count) as u8,
+        )
+        .ok()
+    }
+    /// Serializes the check bits (a nibble per cell, two cells per byte) followed by the visited
+    /// bitmap, leaving out the walls.
+    pub fn checks_to_bytes(&self) -> [u8; CHECKS_BYTES] {
+        let mut bytes = [0; CHECKS_BYTES];
+        for (i, pair) in self.data.chunks(2).enumerate() {
+            bytes[i] = (pair[0].into_bytes()[0] >> 4) | (pair[1].into_bytes()[0] & 0xf0);
+        }
+        bytes[WIDTH * WIDTH / 2..].copy_from_slice(&self.visited);
+        bytes
+    }
+    /// Restores the state written by `checks_to_bytes`. The walls are left untouched.
+    pub fn checks_from_bytes(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        if bytes.len() != CHECKS_BYTES {
+            return Err(Error::ParseError);
+        }
+        for (i, cell) in self.data.iter_mut().enumerate() {
+            let checks = (bytes[i / 2] >> (4 * (i % 2))) & 0x0f;
+            *cell = Cell::from_bytes([(cell.into_bytes()[0] & 0x0f) | (checks << 4)]);
+        }
+        self.visited.copy_from_slice(&bytes[WIDTH * WIDTH / 2..]);
+        Ok(())
+    }
+    /// Encodes the walls compactly for sending over a slow link, e.g. 65 bytes for a 16x16 maze.
+    ///
+    /// The layout is the start and goal coordinates as `x, y` byte pairs and the goal size,
+    /// followed by two bit-planes, LSB first: the north walls of the cells below the top row, then
+    /// the east walls of the cells left of the rightmost column, both in row-major order. Each
+    /// shared wall is stored once and the outer boundary is implied, so check bits and visited
+    /// cells are left out.
+    pub fn to_wall_bytes(&self) -> Vec<u8, WALL_BYTES> {
+        let mut bytes = Vec::<u8, WALL_BYTES>::new();
+        let header = [
+            self.start.x.value,
+            self.start.y.value,
+            self.goal.x.value,
+            self.goal.y.value,
+            self.goal_size,
+        ];
+        bytes.extend_from_slice(&header).unwrap();
+        bytes.resize_default(WALL_BYTES).unwrap();
+        let mut set_bit = |bit: usize| bytes[5 + bit / 8] |= 1 << (bit % 8);
+        for y in 0..WIDTH {
+            for x in 0..WIDTH {
+                let cell = self.data[x + y * WIDTH];
+                if y < WIDTH - 1 && cell.north() {
+                    set_bit(x + y * WIDTH);
+                }
+                if x < WIDTH - 1 && cell.east() {
+                    set_bit(WALL_PLANE_BITS + x + y * (WIDTH - 1));
+                }
+            }
+        }
+        bytes
+    }
+    /// Decodes the walls written by `to_wall_bytes` into a new maze. Fails with
+    /// `Error::ParseError` if the length is wrong or a coordinate is out of the maze, and with
+    /// `Error::OutOfRange` if the goal region does not fit.
+    pub fn from_wall_bytes(bytes: &[u8]) -> Result<Maze, Error> {
+        if bytes.len() != WALL_BYTES || bytes[..4].iter().any(|&v| v as usize >= WIDTH) {
+            return Err(Error::ParseError);
+        }
+        let mut maze = Self::new(
+            CoordXY::new(bytes[0], bytes[1])?,
+            CoordXY::new(bytes[2], bytes[3])?,
+        );
+        maze.set_goal_size(bytes[4])?;
+        let bit = |bit: usize| bytes[5 + bit / 8] & (1 << (bit % 8)) != 0;
+        for y in 0..WIDTH {
+            for x in 0..WIDTH {
+                let coord = CoordXY::new(x as u8, y as u8)?;
+                if y < WIDTH - 1 && bit(x + y * WIDTH) {
+                    maze.set_cell_state(coord, Direction::North, true);
+                }
+                if x < WIDTH - 1 && bit(WALL_PLANE_BITS + x + y * (WIDTH - 1)) {
+                    maze.set_cell_state(coord, Direction::East, true);
+                }
+            }
+        }
+        Ok(maze)
+    }
+    pub fn set_cell_check(&mut self, coord: CoordXY, direction: Direction, state: bool) {
+        self.mutable_cell(coord)
+            .set_check_by_direction(direction, state);
+        if let Ok(next_coord) = coord + direction.into() {
+            self.mutable_cell(next_coord)
+                .set_check_by_direction(direction.inverted(), state);
+        }
+    }
+    /// Returns whether the wall in `direction` from `from` has been checked from either side.
+    pub fn edge_checked(&self, from: CoordXY, direction: Direction) -> bool {
+        // NOTE: the two sides may disagree if a cell was modified directly
+        self.cell(from).check_by_direction(direction)
+            || (from + direction.into())
+                .is_ok_and(|next| self.cell(next).check_by_direction(direction.inverted()))
+    }
+    /// Returns whether all four walls of the cell at `coord` have been checked.
+    pub fn is_explored(&self, coord: CoordXY) -> bool {
+        use Direction::*;
+        let cell = self.cell(coord);
+        [North, East, South, West]
+            .into_iter()
+            .all(|direction| cell.check_by_direction(direction))
+    }
+    /// Forgets everything sensed so far to start a fresh exploration: all walls but the outer
+    /// boundary are removed, and the check bits and visited cells are cleared.
+    pub fn assume_walls_unknown(&mut self) {
+        let (start, goal, goal_size) = (self.start, self.goal, self.goal_size);
+        *self = Self::new(start, goal);
+        self.goal_size = goal_size;
+    }
+    /// Records the walls sensed around the cell at `coord`, given in the order of north, east,
+    /// south and west, and marks them as checked on both sides. Walls on the perimeter of the maze
+    /// stay closed whatever is sensed.
+    pub fn sense(&mut self, coord: CoordXY, walls: [bool; 4]) {
+        use Direction::*;
+        for (direction, wall) in [North, East, South, West].into_iter().zip(walls) {
+            let wall = wall || (coord + direction.into()).is_err();
+            self.set_cell_state(coord, direction, wall);
+            self.set_cell_check(coord, direction, true);
+        }
+    }
+    // Marks `start` with `S`, `goal` with `G` and the rest of the goal region with `g`, from
+    // which `load_from_str` restores the goal size
+    fn cell_mark(&self, coord: CoordXY) -> char {
+        if coord == self.start {
+            'S'
+        } else if coord == self.goal {
+            'G'
+        } else if self.is_goal(coord) {
+            'g'
+        } else {
+            ' '
+        }
+    }
+}
+impl fmt::Display for Maze {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.marks())
+    }
+}
+
+/// Renders a maze in the format of `Display` with cells `cell_width` characters wide, each showing
+/// the string its `content` returns centered. Longer contents are truncated.
+pub struct MazeRenderer<'a, F> {
+    maze: &'a Maze,
+    cell_width: usize,
+    content: F,
+    diff: Option<&'a Maze>,
+}
+impl Maze {
+    /// Returns a renderer for annotating the cells, e.g. with distances or arrows.
+    pub fn renderer<const N: usize, F: Fn(CoordXY) -> heapless::String<N>>(
+        &self,
+        cell_width: usize,
+        content: F,
+    ) -> MazeRenderer<'_, F> {
+        MazeRenderer {
+            maze: self,
+            cell_width,
+            content,
+            diff: None,
+        }
+    }
+    // Renderer showing the marks of `cell_mark`, which `Display` writes
+    fn marks(&self) -> MazeRenderer<'_, impl Fn(CoordXY) -> heapless::String<1> + '_> {
+        self.renderer(3, |coord| {
+            let mut mark = heapless::String::<1>::new();
+            // NOTE: a mark is a single ASCII character
+            mark.push(self.cell_mark(coord)).unwrap();
+            mark
+        })
+    }
+}
+impl<F> MazeRenderer<'_, F> {
+    // Draws the wall on `direction` of the cell at `coord` with `wall` or `open`. Walls which
+    // differ from `diff` are drawn with `#` where present and with `.` where missing.
+    fn wall_mark(&self, coord: CoordXY, direction: Direction, wall: char, open: char) -> char {
+        let own = self.maze.cell(coord).state_by_direction(direction);
+        let theirs = self
+            .diff
+            .map_or(own, |other| other.cell(coord).state_by_direction(direction));
+        match (own, theirs) {
+            (true, true) => wall,
+            (false, false) => open,
+            (true, false) => '#',
+            (false, true) => '.',
+        }
+    }
+}
+impl<const N: usize, F: Fn(CoordXY) -> heapless::String<N>> fmt::Display for MazeRenderer<'_, F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Direction::*;
+        let width = self.cell_width;
+        let coord = |x: usize, y: usize| CoordXY::new(x as u8, y as u8).unwrap();
+        let horizontal = |f: &mut fmt::Formatter, coord: CoordXY, direction: Direction| {
+            let mark = self.wall_mark(coord, direction, '-', ' ');
+            write!(f, "+")?;
+            for _ in 0..width {
+                write!(f, "{mark}")?;
+            }
+            Ok(())
+        };
+        for y in (0..WIDTH).rev() {
+            for x in 0..WIDTH {
+                horizontal(f, coord(x, y), North)?;
+            }
+            writeln!(f, "+")?;
+            for x in 0..WIDTH {
+                let content = (self.content)(coord(x, y));
+                write!(
+                    f,
+                    "{}{:^width$.width$}",
+                    self.wall_mark(coord(x, y), West, '|', ' '),
+                    content.as_str()
+                )?;
+            }
+            writeln!(f, "{}", self.wall_mark(coord(WIDTH - 1, y), East, '|', ' '))?;
+        }
+        for x in 0..WIDTH {
+            horizontal(f, coord(x, 0), South)?;
+        }
+        writeln!(f, "+")
+    }
+}
+#[cfg(feature = "std")]
+impl Maze {
+    /// Writes the maze in the CSV format read by `from_csv`.
+    pub fn to_csv(&self) -> std::string::String {
+        use std::fmt::Write;
+        let mut csv = std::string::String::new();
+        // NOTE: writing to a String never fails
+        writeln!(csv, "{WIDTH},{WIDTH}").unwrap();
+        for y in 0..WIDTH {
+            for x in 0..WIDTH {
+                let cell = self.data[x + y * WIDTH];
+                writeln!(
+                    csv,
+                    "{x},{y},{},{},{},{}",
+                    cell.north() as u8,
+                    cell.east() as u8,
+                    cell.south() as u8,
+                    cell.west() as u8
+                )
+                .unwrap();
+            }
+        }
+        csv
+    }
+    /// Renders `self` and `other` side by side in the format of `Display`. Walls present in only
+    /// one of the mazes are drawn with `#` there and with `.` in the other one.
+    pub fn pretty_diff(&self, other: &Maze) -> std::string::String {
+        let render = |maze: &Maze, other: &Maze| {
+            let renderer = MazeRenderer {
+                diff: Some(other),
+                ..maze.marks()
+            };
+            std::format!("{renderer}")
+        };
+        let (own, theirs) = (render(self, other), render(other, self));
+        let mut out = std::string::String::new();
+        for (own, theirs) in own.lines().zip(theirs.lines()) {
+            out.push_str(own);
+            out.push_str("   ");
+            out.push_str(theirs);
+            out.push('\n');
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAZE_STR: &str = "\
         +   +   +   +   +\n\
         |                \n\
         +   +   +   +   +\n\
@@ -370,6 +1513,22 @@ mod tests {
         |       |        \n\
         +---+---+---+---+\n";
 
+    #[test]
+    fn error_from_try_from_int_error() {
+        let result: Result<u8, Error> = u8::try_from(256u16).map_err(Error::from);
+        assert_eq!(result, Err(Error::OutOfRange));
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn error_into_boxed_std_error() {
+        fn coord(x: u16) -> Result<CoordXY, std::boxed::Box<dyn std::error::Error>> {
+            Ok(CoordXY::new(u8::try_from(x).map_err(Error::from)?, 0)?)
+        }
+        assert!(coord(1).is_ok());
+        let err = coord(256).unwrap_err();
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::OutOfRange));
+        assert_eq!(std::format!("{}", err), "value out of range");
+    }
     #[test]
     fn direction_inverted() {
         assert_eq!(Direction::North.inverted(), Direction::South);
@@ -461,6 +1620,44 @@ mod tests {
         );
     }
     #[test]
+    fn coord_xy_adjacent() {
+        assert_eq!(
+            CoordXY::new(2, 2).unwrap().adjacent(),
+            [
+                CoordXY::new(2, 3).unwrap(),
+                CoordXY::new(3, 2).unwrap(),
+                CoordXY::new(2, 1).unwrap(),
+                CoordXY::new(1, 2).unwrap(),
+            ]
+        );
+        assert_eq!(
+            CoordXY::new(0, 0).unwrap().adjacent(),
+            [CoordXY::new(0, 1).unwrap(), CoordXY::new(1, 0).unwrap()]
+        );
+    }
+    #[test]
+    fn coord_xy_is_diagonal_neighbor() {
+        use Direction::*;
+        let coord = CoordXY::new(2, 2).unwrap();
+        for (x, y, expected) in [
+            (3, 3, (North, East)),
+            (1, 3, (North, West)),
+            (3, 1, (South, East)),
+            (1, 1, (South, West)),
+        ] {
+            assert_eq!(
+                coord.is_diagonal_neighbor(&CoordXY::new(x, y).unwrap()),
+                Some(expected)
+            );
+        }
+        for (x, y) in [(2, 2), (2, 3), (3, 2), (4, 4), (0, 2)] {
+            assert_eq!(
+                coord.is_diagonal_neighbor(&CoordXY::new(x, y).unwrap()),
+                None
+            );
+        }
+    }
+    #[test]
     fn direction_into_vector_xy() {
         assert_eq!(VectorXY { x: 0, y: 1 }, Direction::North.into());
         assert_eq!(VectorXY { x: 1, y: 0 }, Direction::East.into());
@@ -468,6 +1665,182 @@ mod tests {
         assert_eq!(VectorXY { x: -1, y: 0 }, Direction::West.into());
     }
     #[test]
+    fn agent_state_is_at() {
+        let mut maze = Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(7, 7).unwrap());
+        let mut state = AgentState {
+            location: CoordXY::new(0, 0).unwrap(),
+            local_location: CellLocalLocation::Center,
+            heading_vector: Direction::North.into(),
+        };
+        assert!(state.is_at(CoordXY::new(0, 0).unwrap()));
+        assert!(!state.is_at(CoordXY::new(1, 0).unwrap()));
+        assert!(maze.is_home(&state));
+        for turn in [Turn::Left, Turn::Back, Turn::Right] {
+            assert!(maze.is_home(&state.apply_turn(turn)));
+        }
+        state.location = CoordXY::new(0, 1).unwrap();
+        assert!(!maze.is_home(&state));
+        maze.start = state.location;
+        assert!(maze.is_home(&state));
+    }
+    #[test]
+    fn agent_state_apply_turn() {
+        use Direction::*;
+        let state = |direction: Direction| AgentState {
+            location: CoordXY::new(1, 2).unwrap(),
+            local_location: CellLocalLocation::Center,
+            heading_vector: direction.into(),
+        };
+        for (heading, left, right, back) in [
+            (North, West, East, South),
+            (East, North, South, West),
+            (South, East, West, North),
+            (West, South, North, East),
+        ] {
+            assert_eq!(state(heading).apply_turn(Turn::Straight), state(heading));
+            assert_eq!(state(heading).apply_turn(Turn::Left), state(left));
+            assert_eq!(state(heading).apply_turn(Turn::Right), state(right));
+            assert_eq!(state(heading).apply_turn(Turn::Back), state(back));
+        }
+        let still = AgentState {
+            heading_vector: VectorXY { x: 0, y: 0 },
+            ..state(North)
+        };
+        assert_eq!(still.apply_turn(Turn::Left), still);
+    }
+    #[test]
+    fn agent_state_cmp_position() {
+        let state = |x, y, heading_vector| AgentState {
+            location: CoordXY::new(x, y).unwrap(),
+            local_location: CellLocalLocation::Center,
+            heading_vector,
+        };
+        let mut states = [
+            state(1, 1, VectorXY { x: 0, y: 1 }),
+            state(2, 0, VectorXY { x: 1, y: 0 }),
+            state(0, 1, VectorXY { x: 0, y: -1 }),
+            state(0, 0, VectorXY { x: 0, y: 0 }),
+        ];
+        states.sort_by(|a, b| a.cmp_position(b));
+        assert_eq!(states[0].location, CoordXY::new(0, 0).unwrap());
+        assert_eq!(states[1].location, CoordXY::new(2, 0).unwrap());
+        assert_eq!(states[2].location, CoordXY::new(0, 1).unwrap());
+        assert_eq!(states[3].location, CoordXY::new(1, 1).unwrap());
+        assert_eq!(
+            state(3, 2, VectorXY { x: 0, y: 1 }).cmp_position(&state(
+                3,
+                2,
+                VectorXY { x: 1, y: 0 }
+            )),
+            core::cmp::Ordering::Equal
+        );
+    }
+    #[test]
+    fn turn_between() {
+        let north = VectorXY { x: 0, y: 1 };
+        assert_eq!(Turn::between(north, north), Turn::Straight);
+        assert_eq!(Turn::between(north, VectorXY { x: -1, y: 0 }), Turn::Left);
+        assert_eq!(Turn::between(north, VectorXY { x: 1, y: 0 }), Turn::Right);
+        assert_eq!(Turn::between(north, VectorXY { x: 0, y: -1 }), Turn::Back);
+        assert_eq!(
+            Turn::between(VectorXY { x: 1, y: 0 }, VectorXY { x: 0, y: -1 }),
+            Turn::Right
+        );
+    }
+    #[test]
+    fn command_inverted() {
+        assert_eq!(
+            Command::Turn(Turn::Left).inverted(),
+            Command::Turn(Turn::Right)
+        );
+        assert_eq!(
+            Command::Turn(Turn::Right).inverted(),
+            Command::Turn(Turn::Left)
+        );
+        assert_eq!(
+            Command::Turn(Turn::Back).inverted(),
+            Command::Turn(Turn::Back)
+        );
+        assert_eq!(Command::Forward(3).inverted(), Command::Forward(3));
+    }
+    fn execute_commands(
+        commands: &[Command],
+        mut coord: CoordXY,
+        mut heading: Direction,
+    ) -> (CoordXY, Direction) {
+        use Direction::*;
+        for command in commands {
+            match command {
+                Command::Forward(cells) => {
+                    for _ in 0..*cells {
+                        coord = (coord + heading.into()).unwrap();
+                    }
+                }
+                Command::Turn(Turn::Straight) => {}
+                Command::Turn(Turn::Left) => {
+                    heading = match heading {
+                        North => West,
+                        East => North,
+                        South => East,
+                        West => South,
+                    }
+                }
+                Command::Turn(Turn::Right) => {
+                    heading = match heading {
+                        North => East,
+                        East => South,
+                        South => West,
+                        West => North,
+                    }
+                }
+                Command::Turn(Turn::Back) => heading = heading.inverted(),
+            }
+        }
+        (coord, heading)
+    }
+    #[test]
+    fn invert_commands_returns_to_start_pose() {
+        let start = CoordXY::new(2, 0).unwrap();
+        let commands = [
+            Command::Forward(3),
+            Command::Turn(Turn::Left),
+            Command::Forward(2),
+            Command::Turn(Turn::Right),
+            Command::Forward(1),
+            Command::Turn(Turn::Right),
+        ];
+        let (coord, heading) = execute_commands(&commands, start, Direction::North);
+        assert_eq!(coord, CoordXY::new(0, 4).unwrap());
+        assert_eq!(heading, Direction::East);
+
+        let inverted = invert_commands(&commands, Direction::North, Direction::North).unwrap();
+        assert_eq!(inverted.len(), commands.len() + 2);
+        assert_eq!(inverted[1], Command::Turn(Turn::Left));
+        assert_eq!(
+            execute_commands(&inverted, coord, heading),
+            (start, Direction::North)
+        );
+        // The last turn depends on the heading wanted back at the start
+        for final_heading in Direction::iter() {
+            let inverted = invert_commands(&commands, Direction::North, final_heading).unwrap();
+            assert_eq!(
+                execute_commands(&inverted, coord, heading),
+                (start, final_heading)
+            );
+        }
+        let inverted = invert_commands(&commands, Direction::North, Direction::South).unwrap();
+        assert_eq!(inverted.len(), commands.len() + 1);
+    }
+    #[test]
+    fn invert_commands_too_long() {
+        let commands = [Command::Forward(1); MAX_COMMANDS + 1];
+        assert_eq!(
+            invert_commands(&commands, Direction::North, Direction::North).err(),
+            Some(Error::OutOfRange)
+        );
+        assert!(invert_commands(&commands[1..], Direction::North, Direction::North).is_ok());
+    }
+    #[test]
     fn cell_state_by_direction() {
         let mut cell = Cell::new();
         cell.set_east(true);
@@ -498,6 +1871,31 @@ mod tests {
         assert!(!cell.check_east());
     }
     #[test]
+    fn cell_open_directions() {
+        let mut cell = Cell::new();
+        cell.set_east(true);
+        cell.set_west(true);
+        assert_eq!(cell.open_directions(), [Direction::North, Direction::South]);
+    }
+    #[test]
+    fn cell_open_count() {
+        let cell = Cell::new();
+        assert_eq!(cell.open_count(), 4);
+        let cell = cell.with_east(true).with_check_north(true);
+        assert_eq!(cell.open_count(), 3);
+        let cell = cell.with_north(true).with_south(true).with_west(true);
+        assert_eq!(cell.open_count(), 0);
+    }
+    #[test]
+    fn cell_walls_eq() {
+        let cell = Cell::new().with_north(true).with_west(true);
+        let checked = cell.with_check_north(true).with_check_east(true);
+        assert_ne!(cell, checked);
+        assert!(cell.walls_eq(&checked));
+        assert!(checked.walls_eq(&cell));
+        assert!(!cell.walls_eq(&checked.with_south(true)));
+    }
+    #[test]
     fn maze_new() {
         let maze = Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(1, 1).unwrap());
         assert_eq!(maze.start, CoordXY::new(0, 0).unwrap());
@@ -508,6 +1906,37 @@ mod tests {
         assert!(maze.data[0].west());
     }
     #[test]
+    fn maze_is_start_is_goal() {
+        let maze = Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(1, 1).unwrap());
+        assert!(maze.is_start(CoordXY::new(0, 0).unwrap()));
+        assert!(!maze.is_goal(CoordXY::new(0, 0).unwrap()));
+        assert!(maze.is_goal(CoordXY::new(1, 1).unwrap()));
+        assert!(!maze.is_start(CoordXY::new(1, 1).unwrap()));
+        assert!(!maze.is_start(CoordXY::new(1, 0).unwrap()));
+        assert!(!maze.is_goal(CoordXY::new(1, 0).unwrap()));
+    }
+    #[test]
+    fn maze_goal_cells() {
+        let mut maze = Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(3, 3).unwrap());
+        assert_eq!(maze.goal_cells().count(), 1);
+        assert_eq!(maze.goal_cells().next(), Some(maze.goal));
+
+        maze.set_goal_size(2).unwrap();
+        let cells: Vec<CoordXY, 4> = maze.goal_cells().collect();
+        assert_eq!(
+            cells,
+            [(3, 3), (4, 3), (3, 4), (4, 4)].map(|(x, y)| CoordXY::new(x, y).unwrap())
+        );
+        assert!(cells.iter().all(|&coord| maze.is_goal(coord)));
+        assert!(!maze.is_goal(CoordXY::new(5, 4).unwrap()));
+        assert!(!maze.is_goal(CoordXY::new(2, 3).unwrap()));
+
+        assert_eq!(maze.set_goal_size(0), Err(Error::OutOfRange));
+        maze.goal = CoordXY::new(WIDTH as u8 - 1, 0).unwrap();
+        assert_eq!(maze.set_goal_size(2), Err(Error::OutOfRange));
+        assert!(maze.set_goal_size(1).is_ok());
+    }
+    #[test]
     fn maze_cell() {
         let mut maze = Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(7, 7).unwrap());
         maze.data[0].set_north(true);
@@ -539,8 +1968,634 @@ mod tests {
             .south());
     }
     #[test]
+    fn maze_set_cell() {
+        let mut maze = Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(7, 7).unwrap());
+        let coord = CoordXY::new(2, 2).unwrap();
+        let cell = Cell::new()
+            .with_north(true)
+            .with_west(true)
+            .with_check_north(true);
+        maze.set_cell(coord, cell);
+        assert_eq!(maze.cell(coord), cell);
+        for direction in [
+            Direction::North,
+            Direction::East,
+            Direction::South,
+            Direction::West,
+        ] {
+            let neighbor = maze.cell((coord + direction.into()).unwrap());
+            assert_eq!(
+                neighbor.state_by_direction(direction.inverted()),
+                cell.state_by_direction(direction)
+            );
+            assert_eq!(
+                neighbor.check_by_direction(direction.inverted()),
+                cell.check_by_direction(direction)
+            );
+        }
+    }
+    #[test]
+    fn maze_apply_updates() {
+        let mut maze = Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(7, 7).unwrap());
+        maze.set_cell_state(CoordXY::new(2, 2).unwrap(), Direction::East, true);
+        let affected = maze.apply_updates(&[
+            (CoordXY::new(0, 0).unwrap(), Direction::North, true),
+            // Already set
+            (CoordXY::new(2, 2).unwrap(), Direction::East, true),
+            // Perimeter wall, no neighbor
+            (CoordXY::new(1, 0).unwrap(), Direction::South, false),
+            // Same wall as the first update seen from the other side
+            (CoordXY::new(0, 1).unwrap(), Direction::South, true),
+            (CoordXY::new(0, 1).unwrap(), Direction::East, true),
+        ]);
+        assert_eq!(
+            affected,
+            [
+                CoordXY::new(0, 0).unwrap(),
+                CoordXY::new(0, 1).unwrap(),
+                CoordXY::new(1, 0).unwrap(),
+                CoordXY::new(1, 1).unwrap(),
+            ]
+        );
+        assert!(maze.cell(CoordXY::new(0, 1).unwrap()).south());
+        assert!(!maze.cell(CoordXY::new(1, 0).unwrap()).south());
+        assert!(maze.cell(CoordXY::new(1, 1).unwrap()).west());
+    }
+    #[test]
+    fn maze_junction_type() {
+        let mut maze = Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(7, 7).unwrap());
+        let coord = CoordXY::new(2, 2).unwrap();
+        assert_eq!(maze.junction_type(coord), JunctionType::FourWay);
+        maze.set_cell_state(coord, Direction::North, true);
+        assert_eq!(maze.junction_type(coord), JunctionType::ThreeWay);
+        maze.set_cell_state(coord, Direction::South, true);
+        assert_eq!(maze.junction_type(coord), JunctionType::Corridor);
+        maze.set_cell_state(coord, Direction::South, false);
+        maze.set_cell_state(coord, Direction::East, true);
+        assert_eq!(maze.junction_type(coord), JunctionType::Corner);
+        maze.set_cell_state(coord, Direction::South, true);
+        assert_eq!(maze.junction_type(coord), JunctionType::DeadEnd);
+        maze.set_cell_state(coord, Direction::West, true);
+        assert_eq!(maze.junction_type(coord), JunctionType::Closed);
+    }
+    #[test]
+    fn maze_openness_mask() {
+        let maze = Maze::load_from_str(MAZE_STR).unwrap();
+        for (x, y) in [(0, 0), (1, 0), (2, 0), (3, 3), (1, 2)] {
+            let coord = CoordXY::new(x, y).unwrap();
+            let mut expected = 0;
+            for direction in maze.cell(coord).open_directions() {
+                expected |= match direction {
+                    Direction::North => 0b0001,
+                    Direction::East => 0b0010,
+                    Direction::South => 0b0100,
+                    Direction::West => 0b1000,
+                };
+            }
+            assert_eq!(maze.openness_mask(coord), expected);
+        }
+        assert_eq!(maze.openness_mask(CoordXY::new(0, 0).unwrap()), 0b0010);
+    }
+    #[test]
+    fn maze_wall_hamming() {
+        let maze = Maze::load_from_str(MAZE_STR).unwrap();
+        let mut other = Maze::load_from_str(MAZE_STR).unwrap();
+        assert_eq!(maze.wall_hamming(&other), 0);
+        other.set_cell_state(CoordXY::new(2, 2).unwrap(), Direction::West, true);
+        assert_eq!(maze.wall_hamming(&other), 1);
+        assert_eq!(other.wall_hamming(&maze), 1);
+        // Perimeter walls are not counted
+        other.set_cell_state(CoordXY::new(0, 0).unwrap(), Direction::South, false);
+        assert_eq!(maze.wall_hamming(&other), 1);
+        other.set_cell_state(CoordXY::new(0, 0).unwrap(), Direction::North, false);
+        assert_eq!(maze.wall_hamming(&other), 2);
+    }
+    #[test]
+    fn maze_wall_segments() {
+        let maze = Maze::load_from_str(MAZE_STR).unwrap();
+        assert_eq!(maze.wall_segments().count(), 2 * WIDTH * (WIDTH - 1));
+        let mut segments = maze.wall_segments();
+        assert_eq!(
+            segments.next(),
+            Some((CoordXY::new(0, 0).unwrap(), Direction::East, false))
+        );
+        assert_eq!(
+            segments.next(),
+            Some((CoordXY::new(0, 0).unwrap(), Direction::North, true))
+        );
+        assert!(maze
+            .wall_segments()
+            .all(
+                |(coord, direction, state)| maze.cell(coord).state_by_direction(direction) == state
+            ));
+        assert_eq!(
+            maze.wall_segments().filter(|&(_, _, state)| state).count(),
+            3
+        );
+    }
+    #[test]
+    fn maze_checks_bytes_round_trip() {
+        let mut maze = Maze::load_from_str(MAZE_STR).unwrap();
+        maze.set_cell_check(CoordXY::new(0, 0).unwrap(), Direction::North, true);
+        maze.set_cell_check(CoordXY::new(1, 0).unwrap(), Direction::East, true);
+        let corner = CoordXY::new(WIDTH as u8 - 1, WIDTH as u8 - 1).unwrap();
+        maze.set_cell_check(corner, Direction::West, true);
+        maze.set_visited(CoordXY::new(1, 0).unwrap(), true);
+        let bytes = maze.checks_to_bytes();
+
+        let mut restored = Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(7, 7).unwrap());
+        let walls = restored.data;
+        restored.checks_from_bytes(&bytes).unwrap();
+        for (i, cell) in restored.data.iter().enumerate() {
+            assert!(cell.walls_eq(&walls[i]));
+            assert_eq!(cell.into_bytes()[0] >> 4, maze.data[i].into_bytes()[0] >> 4);
+        }
+        assert!(restored.is_visited(CoordXY::new(1, 0).unwrap()));
+        assert!(!restored.is_visited(CoordXY::new(0, 0).unwrap()));
+        assert!(restored.cell(CoordXY::new(2, 0).unwrap()).check_west());
+        assert_eq!(restored.checks_to_bytes(), bytes);
+
+        assert_eq!(
+            restored.checks_from_bytes(&bytes[1..]).err(),
+            Some(Error::ParseError)
+        );
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn maze_pretty_diff() {
+        let maze = Maze::load_from_str(MAZE_STR).unwrap();
+        let mut other = Maze::load_from_str(MAZE_STR).unwrap();
+        assert!(!maze.pretty_diff(&other).contains(['#', '.']));
+
+        other.set_cell_state(CoordXY::new(2, 2).unwrap(), Direction::North, true);
+        other.set_cell_state(CoordXY::new(1, 0).unwrap(), Direction::East, false);
+        let diff = maze.pretty_diff(&other);
+        assert_eq!(diff.lines().count(), 2 * WIDTH + 1);
+        assert_eq!(diff.matches("...").count(), 1);
+        assert_eq!(diff.matches("###").count(), 1);
+        assert_eq!(diff.matches('#').count(), 4);
+        assert_eq!(diff.matches('.').count(), 4);
+        // The wall east of (1, 0) only exists on the left
+        let bottom_row = diff.lines().nth(2 * WIDTH - 1).unwrap();
+        assert_eq!(bottom_row.find('#'), Some(8));
+        assert_eq!(bottom_row.rfind('.'), Some(4 * WIDTH + 1 + 3 + 8));
+    }
+    #[test]
+    fn maze_to_str_round_trip() {
+        use core::fmt::Write;
+        let mut maze = Maze::load_from_str(MAZE_STR).unwrap();
+        maze.start = CoordXY::new(1, 0).unwrap();
+        maze.goal = CoordXY::new(2, 2).unwrap();
+        maze.set_goal_size(2).unwrap();
+        let maze_str = maze.to_str();
+        assert_eq!(maze_str.len(), MAZE_STR_LEN);
+        assert_eq!(maze_str.matches('G').count(), 1);
+        assert_eq!(maze_str.matches('g').count(), 3);
+        // Display marks the cells the same way, so the goal survives loading its output too
+        let mut displayed = heapless::String::<MAZE_STR_LEN>::new();
+        write!(displayed, "{maze}").unwrap();
+        assert_eq!(displayed, maze_str);
+
+        let loaded = Maze::load_from_str(&maze_str).unwrap();
+        assert_eq!(loaded.data, maze.data);
+        assert_eq!(loaded.start, maze.start);
+        assert_eq!(loaded.goal, maze.goal);
+        assert_eq!(loaded.goal_cells().count(), 4);
+        assert_eq!(loaded.to_str(), maze_str);
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn maze_renderer() {
+        use core::fmt::Write;
+        let maze = Maze::load_from_str(MAZE_STR).unwrap();
+        // The default rendering is the special case of three-character-wide cells
+        let marks = maze.renderer(3, |coord| {
+            let mut s = heapless::String::<1>::new();
+            s.push(maze.cell_mark(coord)).unwrap();
+            s
+        });
+        assert_eq!(std::format!("{marks}"), std::format!("{maze}"));
+
+        // Distances from the bottom-left corner in an open maze
+        let maze = Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(7, 7).unwrap());
+        let distances = maze.renderer(4, |coord| {
+            let mut s = heapless::String::<4>::new();
+            write!(s, "{}", coord.x().value() + coord.y().value()).unwrap();
+            s
+        });
+        let rendered = std::format!("{distances}");
+        let lines: std::vec::Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines.len(), 2 * WIDTH + 1);
+        assert!(lines.iter().all(|l| l.len() == 5 * WIDTH + 1));
+        assert!(lines[2 * WIDTH - 1].starts_with("| 0    1    2  "));
+        let last = std::format!("{:^4}|", WIDTH - 1);
+        assert!(lines[2 * WIDTH - 1].ends_with(&last));
+        // The farthest cell is at the top right
+        let farthest = std::format!(" {} |", 2 * (WIDTH - 1));
+        assert!(lines[1].ends_with(&farthest));
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn maze_csv_round_trip() {
+        let maze = Maze::load_from_str(MAZE_STR).unwrap();
+        let csv = maze.to_csv();
+        assert!(csv.starts_with(&std::format!("{WIDTH},{WIDTH}\n0,0,1,0,1,1\n")));
+        assert_eq!(csv.lines().count(), WIDTH * WIDTH + 1);
+        assert_eq!(Maze::from_csv(&csv).unwrap().data, maze.data);
+    }
+    #[test]
+    fn maze_from_csv_malformed() {
+        use core::fmt::Write;
+        assert_eq!(Maze::from_csv("").err(), Some(Error::ParseError));
+        // Size differs from WIDTH
+        assert_eq!(
+            Maze::from_csv("1,1\n0,0,1,1,1,1\n").err(),
+            Some(Error::ParseError)
+        );
+        // Missing cells
+        let mut csv = heapless::String::<64>::new();
+        writeln!(csv, "{WIDTH},{WIDTH}").unwrap();
+        csv.push_str("0,0,0,0,1,1\n").unwrap();
+        assert_eq!(Maze::from_csv(&csv).err(), Some(Error::ParseError));
+        // Wrong number of fields
+        csv.push_str("0,1,0,0,1\n").unwrap();
+        assert_eq!(Maze::from_csv(&csv).err(), Some(Error::ParseError));
+    }
+    #[test]
+    fn maze_from_csv_inconsistent() {
+        use core::fmt::Write;
+        let maze = Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(7, 7).unwrap());
+        let mut csv = heapless::String::<{ 16 * WIDTH * WIDTH }>::new();
+        writeln!(csv, "{WIDTH},{WIDTH}").unwrap();
+        for y in 0..WIDTH {
+            for x in 0..WIDTH {
+                let cell = maze.data[x + y * WIDTH];
+                // A wall on the east of (0, 0) only
+                let east = cell.east() || (x, y) == (0, 0);
+                writeln!(
+                    csv,
+                    "{x},{y},{},{},{},{}",
+                    cell.north() as u8,
+                    east as u8,
+                    cell.south() as u8,
+                    cell.west() as u8
+                )
+                .unwrap();
+            }
+        }
+        assert_eq!(Maze::from_csv(&csv).err(), Some(Error::ParseError));
+    }
+    #[test]
+    fn log2_fixed_point() {
+        assert_eq!(log2_x256(1), 0);
+        assert_eq!(log2_x256(8), 3 * 256);
+        // log2(3) = 1.58496...
+        assert_eq!(log2_x256(3), 405);
+    }
+    #[test]
+    fn maze_wall_entropy() {
+        use crate::generator::generate_backtracker;
+        use rand_pcg::Pcg32;
+
+        let mut closed = Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(7, 7).unwrap());
+        for y in 0..WIDTH as u8 {
+            for x in 0..WIDTH as u8 {
+                closed.set_cell(
+                    CoordXY::new(x, y).unwrap(),
+                    Cell::new()
+                        .with_north(true)
+                        .with_east(true)
+                        .with_south(true)
+                        .with_west(true),
+                );
+            }
+        }
+        assert_eq!(closed.wall_entropy(), 0);
+        // Mostly open cells with a few kinds on the perimeter
+        let open = Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(7, 7).unwrap());
+        assert!(open.wall_entropy() > 0);
+        let mut rng = Pcg32::new(0xcafef00dd15ea5e5, 0xa02bdbf7bb3c0a7);
+        let generated = generate_backtracker(
+            &mut rng,
+            CoordXY::new(0, 0).unwrap(),
+            CoordXY::new(7, 7).unwrap(),
+        );
+        assert!(generated.wall_entropy() > open.wall_entropy());
+        assert!(generated.wall_entropy() <= 1024);
+    }
+    #[test]
+    fn maze_edge_checked() {
+        let mut maze = Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(7, 7).unwrap());
+        let coord = CoordXY::new(1, 1).unwrap();
+        let next = CoordXY::new(2, 1).unwrap();
+        assert!(!maze.edge_checked(coord, Direction::East));
+        // Only the far side is updated
+        maze.mutable_cell(next)
+            .set_check_by_direction(Direction::West, true);
+        assert!(!maze.cell(coord).check_east());
+        assert!(maze.edge_checked(coord, Direction::East));
+        assert!(maze.edge_checked(next, Direction::West));
+        assert!(!maze.edge_checked(coord, Direction::North));
+        // The perimeter has no far side
+        let corner = CoordXY::new(0, 0).unwrap();
+        assert!(!maze.edge_checked(corner, Direction::South));
+        maze.set_cell_check(corner, Direction::South, true);
+        assert!(maze.edge_checked(corner, Direction::South));
+    }
+    #[test]
+    fn maze_step() {
+        let mut maze = Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(7, 7).unwrap());
+        let coord = CoordXY::new(1, 1).unwrap();
+        assert_eq!(
+            maze.step(coord, Direction::North),
+            Some(CoordXY::new(1, 2).unwrap())
+        );
+        maze.set_cell_state(coord, Direction::North, true);
+        assert_eq!(maze.step(coord, Direction::North), None);
+        assert_eq!(
+            maze.step(CoordXY::new(0, 0).unwrap(), Direction::South),
+            None
+        );
+    }
+    #[test]
+    fn maze_line_of_sight() {
+        let mut maze = Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(7, 7).unwrap());
+        maze.set_cell_state(CoordXY::new(3, 1).unwrap(), Direction::East, true);
+        assert_eq!(
+            maze.line_of_sight(CoordXY::new(0, 1).unwrap(), Direction::East),
+            [(1, 1), (2, 1), (3, 1)].map(|(x, y)| CoordXY::new(x, y).unwrap())
+        );
+        // Up to the perimeter
+        assert_eq!(
+            maze.line_of_sight(CoordXY::new(0, 1).unwrap(), Direction::North)
+                .len(),
+            WIDTH - 2
+        );
+        // Facing a wall
+        assert!(maze
+            .line_of_sight(CoordXY::new(3, 1).unwrap(), Direction::East)
+            .is_empty());
+        assert!(maze
+            .line_of_sight(CoordXY::new(0, 1).unwrap(), Direction::West)
+            .is_empty());
+    }
+    #[test]
+    fn maze_reachable_cells() {
+        let mut maze = Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(7, 7).unwrap());
+        assert_eq!(maze.reachable_cells(maze.start).len(), WIDTH * WIDTH);
+        assert!(maze.is_reachable(maze.start, maze.goal));
+
+        // Wall off (2, 2) as an island
+        let island = CoordXY::new(2, 2).unwrap();
+        use Direction::*;
+        for direction in [North, East, South, West] {
+            maze.set_cell_state(island, direction, true);
+        }
+        assert!(!maze.is_reachable(maze.start, island));
+        assert!(!maze.is_reachable(island, maze.goal));
+        assert!(maze.is_reachable(island, island));
+        assert_eq!(maze.reachable_cells(island), [island]);
+        let cells = maze.reachable_cells(maze.start);
+        assert_eq!(cells.len(), WIDTH * WIDTH - 1);
+        assert_eq!(cells[0], maze.start);
+        assert!(!cells.contains(&island));
+    }
+    #[test]
+    fn maze_reset_region() {
+        let mut maze = Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(7, 7).unwrap());
+        let closed = Cell::new()
+            .with_north(true)
+            .with_east(true)
+            .with_south(true)
+            .with_west(true)
+            .with_check_north(true)
+            .with_check_east(true)
+            .with_check_south(true)
+            .with_check_west(true);
+        for y in 0..4 {
+            for x in 0..4 {
+                maze.set_cell(CoordXY::new(x, y).unwrap(), closed);
+            }
+        }
+        maze.reset_region(CoordXY::new(1, 1).unwrap(), 2).unwrap();
+        // Only the walls on the boundary of the region are left
+        let cell = maze.cell(CoordXY::new(1, 1).unwrap());
+        assert!(!cell.north() && !cell.east() && cell.south() && cell.west());
+        assert!(
+            !cell.check_north() && !cell.check_east() && cell.check_south() && cell.check_west()
+        );
+        let cell = maze.cell(CoordXY::new(2, 2).unwrap());
+        assert!(cell.north() && cell.east() && !cell.south() && !cell.west());
+        // Outside of the region
+        assert_eq!(maze.cell(CoordXY::new(0, 1).unwrap()), closed);
+        assert_eq!(maze.cell(CoordXY::new(3, 3).unwrap()), closed);
+        assert_eq!(maze.cell(CoordXY::new(1, 0).unwrap()), closed);
+
+        assert_eq!(
+            maze.reset_region(CoordXY::new(WIDTH as u8 - 2, 0).unwrap(), 3),
+            Err(Error::OutOfRange)
+        );
+        assert!(maze
+            .reset_region(CoordXY::new(WIDTH as u8 - 2, 0).unwrap(), 2)
+            .is_ok());
+    }
+    #[test]
+    fn maze_upscale() {
+        use crate::graph::four_way_grid::Graph;
+        let maze = Maze::load_from_str(
+            "\
+            +---+---+---+---+\n\
+            |       |     G |\n\
+            +   +---+   +   +\n\
+            |           |   |\n\
+            +---+   +---+   +\n\
+            |   |           |\n\
+            +   +---+---+---+\n\
+            | S             |\n\
+            +---+---+---+---+\n",
+        )
+        .unwrap();
+        let upscaled = maze.upscale();
+        assert_eq!(upscaled.start, CoordXY::new(0, 0).unwrap());
+        assert_eq!(upscaled.goal, CoordXY::new(6, 6).unwrap());
+        // Lower-left cell of the block of (1, 1), open toward the rest of the block
+        let cell = upscaled.cell(CoordXY::new(2, 2).unwrap());
+        assert!(!cell.north() && !cell.east() && cell.south() && cell.west());
+        // Original wall east of (1, 3) on both halves of the block boundary
+        assert!(upscaled.cell(CoordXY::new(3, 6).unwrap()).east());
+        assert!(upscaled.cell(CoordXY::new(3, 7).unwrap()).east());
+
+        let original = Graph { maze };
+        let mut reachable = [false; WIDTH * WIDTH];
+        for coord in original.maze.reachable(&original, original.maze.start) {
+            reachable[coord.x.value as usize + coord.y.value as usize * WIDTH] = true;
+        }
+        let graph = Graph { maze: upscaled };
+        let mut upscaled_reachable = [false; WIDTH * WIDTH];
+        for coord in graph.maze.reachable(&graph, graph.maze.start) {
+            upscaled_reachable[coord.x.value as usize + coord.y.value as usize * WIDTH] = true;
+        }
+        for y in 0..WIDTH {
+            for x in 0..WIDTH {
+                assert_eq!(
+                    upscaled_reachable[x + y * WIDTH],
+                    reachable[x / 2 + y / 2 * WIDTH]
+                );
+            }
+        }
+        // (1, 1) is cut off from the start
+        assert!(!reachable[1 + WIDTH]);
+    }
+    #[test]
+    fn maze_visited() {
+        let mut maze = Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(7, 7).unwrap());
+        let coord = CoordXY::new(3, 2).unwrap();
+        assert!(!maze.is_visited(coord));
+        maze.set_visited(coord, true);
+        assert!(maze.is_visited(coord));
+        assert!(!maze.is_visited(CoordXY::new(2, 3).unwrap()));
+        maze.set_visited(coord, false);
+        assert!(!maze.is_visited(coord));
+    }
+    #[test]
+    fn maze_explored_centroid() {
+        let mut maze = Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(7, 7).unwrap());
+        assert_eq!(maze.explored_centroid(), None);
+        for (x, y) in [(0, 0), (1, 0), (2, 0), (2, 1), (2, 2)] {
+            maze.set_visited(CoordXY::new(x, y).unwrap(), true);
+        }
+        // x: 7 / 5 = 1.4, y: 3 / 5 = 0.6
+        assert_eq!(maze.explored_centroid(), Some(CoordXY::new(1, 1).unwrap()));
+        maze.set_visited(CoordXY::new(4, 4).unwrap(), true);
+        // x: 11 / 6 = 1.83, y: 7 / 6 = 1.17
+        assert_eq!(maze.explored_centroid(), Some(CoordXY::new(2, 1).unwrap()));
+    }
+    #[test]
+    fn maze_load_from_str_malformed() {
+        assert_eq!(Maze::load_from_str("").err(), Some(Error::ParseError));
+        // Correct length but short lines
+        let maze_str = [b'\n'; MAZE_STR.len()];
+        assert_eq!(
+            Maze::load_from_str(core::str::from_utf8(&maze_str).unwrap()).err(),
+            Some(Error::ParseError)
+        );
+        // Truncated in the middle of a line
+        assert_eq!(
+            Maze::load_from_str(&MAZE_STR[..MAZE_STR.len() / 2]).err(),
+            Some(Error::ParseError)
+        );
+        // Garbage of the correct length
+        let maze_str = [b'x'; MAZE_STR.len()];
+        assert_eq!(
+            Maze::load_from_str(core::str::from_utf8(&maze_str).unwrap()).err(),
+            Some(Error::ParseError)
+        );
+        let maze_str = [b'|'; MAZE_STR.len()];
+        assert_eq!(
+            Maze::load_from_str(core::str::from_utf8(&maze_str).unwrap()).err(),
+            Some(Error::ParseError)
+        );
+    }
+    #[test]
+    fn maze_load_from_str_any_width() {
+        let maze = Maze::load_from_str(
+            "\
+            +---+---+---+---+---+\n\
+            |                 G |\n\
+            +   +---+---+---+   +\n\
+            |   |           |   |\n\
+            +   +   +---+   +   +\n\
+            |   |   |       |   |\n\
+            +   +   +   +---+   +\n\
+            |       |           |\n\
+            +---+   +---+---+   +\n\
+            | S                 |\n\
+            +---+---+---+---+---+\n",
+        )
+        .unwrap();
+        assert_eq!(maze.start, CoordXY::new(0, 0).unwrap());
+        assert_eq!(maze.goal, CoordXY::new(4, 4).unwrap());
+        assert!(maze.cell(CoordXY::new(0, 0).unwrap()).north());
+        assert!(!maze.cell(CoordXY::new(1, 0).unwrap()).north());
+        assert!(maze.cell(CoordXY::new(3, 2).unwrap()).south());
+        assert!(maze.cell(CoordXY::new(4, 2).unwrap()).west());
+        assert!(!maze.cell(CoordXY::new(4, 2).unwrap()).south());
+    }
+    #[test]
+    fn maze_load_from_str_invalid_length() {
+        // One character too many
+        let mut maze_str = heapless::String::<{ MAZE_STR.len() + 1 }>::new();
+        maze_str.push_str(MAZE_STR).unwrap();
+        maze_str.push('\n').unwrap();
+        assert_eq!(
+            Maze::load_from_str(&maze_str).err(),
+            Some(Error::ParseError)
+        );
+        // One character too few
+        assert_eq!(
+            Maze::load_from_str(&MAZE_STR[1..]).err(),
+            Some(Error::ParseError)
+        );
+    }
+    #[test]
+    fn maze_load_from_box_drawing_str() {
+        let ascii = Maze::load_from_str(
+            "\
+            +---+---+---+---+\n\
+            |       |     G |\n\
+            +   +---+   +   +\n\
+            |           |   |\n\
+            +---+   +---+   +\n\
+            |   |           |\n\
+            +   +   +---+---+\n\
+            | S             |\n\
+            +---+---+---+---+\n",
+        )
+        .unwrap();
+        let unicode = Maze::load_from_str(
+            "\
+            ┌───┬───┬───┬───┐\n\
+            │       │     G │\n\
+            ├   ┼───┼   ┼   ┤\n\
+            │           │   │\n\
+            ├───┼   ┼───┼   ┤\n\
+            │   │           │\n\
+            ├   ┼   ┼───┼───┤\n\
+            │ S             │\n\
+            └───┴───┴───┴───┘\n",
+        )
+        .unwrap();
+        assert_eq!(unicode.data, ascii.data);
+        assert_eq!(unicode.start, ascii.start);
+        assert_eq!(unicode.goal, ascii.goal);
+        assert!(unicode.cell(CoordXY::new(1, 3).unwrap()).east());
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn maze_load_from_reader() {
+        let maze = Maze::load_from_reader(std::io::Cursor::new(MAZE_STR.as_bytes())).unwrap();
+        assert!(maze
+            .cell_by_x_y(Coord1D::new(1).unwrap(), Coord1D::new(0).unwrap())
+            .east());
+        let invalid = Maze::load_from_reader(std::io::Cursor::new(&[0xffu8, 0xfe][..]));
+        assert_eq!(invalid.err(), Some(Error::IoError));
+    }
+    #[test]
+    fn maze_new_checked() {
+        let start = CoordXY::new(0, 0).unwrap();
+        let goal = CoordXY::new(7, 7).unwrap();
+        assert_eq!(
+            Maze::new_checked(start, start).err(),
+            Some(Error::InvalidLocation)
+        );
+        let maze = Maze::new_checked(start, goal).unwrap();
+        assert_eq!(maze.start, start);
+        assert_eq!(maze.goal, goal);
+        assert_eq!(maze.data, Maze::new(start, goal).data);
+    }
+    #[test]
     fn maze_load() {
-        let maze = Maze::load_from_str(MAZE_STR);
+        let maze = Maze::load_from_str(MAZE_STR).unwrap();
         assert!(maze
             .cell_by_x_y(Coord1D::new(0).unwrap(), Coord1D::new(0).unwrap())
             .north());
@@ -551,4 +2606,373 @@ mod tests {
             .cell_by_x_y(Coord1D::new(1).unwrap(), Coord1D::new(0).unwrap())
             .east());
     }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn maze_serde_round_trip() {
+        let mut maze = Maze::load_from_str(MAZE_STR).unwrap();
+        maze.goal = CoordXY::new(2, 2).unwrap();
+        maze.set_goal_size(2).unwrap();
+        maze.set_cell_check(CoordXY::new(1, 0).unwrap(), Direction::East, true);
+        maze.set_visited(CoordXY::new(3, 1).unwrap(), true);
+
+        let json = serde_json::to_string(&maze).unwrap();
+        let loaded: Maze = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.data, maze.data);
+        assert_eq!(loaded.start, maze.start);
+        assert_eq!(loaded.goal, maze.goal);
+        assert!(loaded.is_goal(CoordXY::new(3, 3).unwrap()));
+        assert!(loaded.is_visited(CoordXY::new(3, 1).unwrap()));
+        assert!(!loaded.is_visited(CoordXY::new(1, 3).unwrap()));
+
+        let vector = VectorXY { x: -1, y: 2 };
+        let json = serde_json::to_string(&(vector, Direction::West)).unwrap();
+        assert_eq!(
+            serde_json::from_str::<(VectorXY, Direction)>(&json).unwrap(),
+            (vector, Direction::West)
+        );
+        assert!(serde_json::from_str::<Maze>("{}").is_err());
+    }
+    #[test]
+    fn maze_wall_bytes_round_trip() {
+        let mut maze = Maze::load_from_str(MAZE_STR).unwrap();
+        maze.goal = CoordXY::new(2, 2).unwrap();
+        maze.set_goal_size(2).unwrap();
+        let bytes = maze.to_wall_bytes();
+        assert_eq!(bytes.len(), WALL_BYTES);
+        assert_eq!(&bytes[..5], &[0, 0, 2, 2, 2]);
+
+        let loaded = Maze::from_wall_bytes(&bytes).unwrap();
+        assert_eq!(loaded.data, maze.data);
+        assert_eq!(loaded.start, maze.start);
+        assert_eq!(loaded.goal, maze.goal);
+        assert!(loaded.is_goal(CoordXY::new(3, 3).unwrap()));
+
+        assert_eq!(
+            Maze::from_wall_bytes(&bytes[..WALL_BYTES - 1]).err(),
+            Some(Error::ParseError)
+        );
+        let mut bytes = bytes;
+        bytes[2] = WIDTH as u8;
+        assert_eq!(Maze::from_wall_bytes(&bytes).err(), Some(Error::ParseError));
+    }
+    #[test]
+    fn maze_wall_bytes_consistent() {
+        let mut rng = rand_pcg::Pcg32::new(0xcafef00dd15ea5e5, 0xa02bdbf7bb3c0a7);
+        let mut bytes =
+            Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(1, 1).unwrap()).to_wall_bytes();
+        rand_core::RngCore::fill_bytes(&mut rng, &mut bytes[5..]);
+        let maze = Maze::from_wall_bytes(&bytes).unwrap();
+        for y in 0..WIDTH as u8 {
+            for x in 0..WIDTH as u8 {
+                let coord = CoordXY::new(x, y).unwrap();
+                if y == Coord1D::MAX {
+                    assert!(maze.cell(coord).north());
+                } else {
+                    let above = CoordXY::new(x, y + 1).unwrap();
+                    assert_eq!(maze.cell(coord).north(), maze.cell(above).south());
+                }
+                if x == Coord1D::MAX {
+                    assert!(maze.cell(coord).east());
+                } else {
+                    let right = CoordXY::new(x + 1, y).unwrap();
+                    assert_eq!(maze.cell(coord).east(), maze.cell(right).west());
+                }
+            }
+        }
+        assert_eq!(maze.to_wall_bytes(), bytes);
+    }
+    #[test]
+    fn maze_validate() {
+        let mut maze = Maze::load_from_str(MAZE_STR).unwrap();
+        assert_eq!(maze.validate(), Ok(()));
+        let bytes = maze.to_wall_bytes();
+        assert_eq!(Maze::from_wall_bytes(&bytes).unwrap().validate(), Ok(()));
+
+        // Break the wall between (1, 0) and (1, 1) on the lower side only
+        let coord = CoordXY::new(1, 0).unwrap();
+        maze.mutable_cell(coord).set_north(false);
+        assert_eq!(
+            maze.validate(),
+            Err(Error::InconsistentWall(coord, Direction::North))
+        );
+        maze.set_cell_state(coord, Direction::North, false);
+        assert_eq!(maze.validate(), Ok(()));
+
+        // Open the outer boundary
+        let coord = CoordXY::new(Coord1D::MAX, 2).unwrap();
+        maze.mutable_cell(coord).set_east(false);
+        assert_eq!(
+            maze.validate(),
+            Err(Error::InconsistentWall(coord, Direction::East))
+        );
+    }
+    #[test]
+    fn maze_xor_walls() {
+        let maze = Maze::load_from_str(MAZE_STR).unwrap();
+        let empty = maze.xor_walls(&maze);
+        assert!(empty.data.iter().all(|cell| cell.open_count() == 4));
+        assert_eq!(empty.start, maze.start);
+
+        let mut other = Maze::load_from_str(MAZE_STR).unwrap();
+        let coord = CoordXY::new(2, 1).unwrap();
+        other.set_cell_state(coord, Direction::East, true);
+        let diff = maze.xor_walls(&other);
+        assert_eq!(diff.wall_segments().filter(|&(_, _, wall)| wall).count(), 1);
+        assert!(diff.cell(coord).east());
+        assert!(diff.cell(CoordXY::new(3, 1).unwrap()).west());
+        assert_eq!(diff.xor_walls(&other).data, maze.data);
+    }
+    #[test]
+    fn coord_xy_transforms() {
+        let coord = CoordXY::new(1, 2).unwrap();
+        let max = Coord1D::MAX;
+        assert_eq!(coord.rotated_cw(), CoordXY::new(2, max - 1).unwrap());
+        assert_eq!(coord.rotated_ccw(), CoordXY::new(max - 2, 1).unwrap());
+        assert_eq!(coord.mirrored_x(), CoordXY::new(max - 1, 2).unwrap());
+        assert_eq!(coord.rotated_cw().rotated_ccw(), coord);
+        assert_eq!(coord.mirrored_x().mirrored_x(), coord);
+        let corner = CoordXY::new(0, max).unwrap();
+        assert_eq!(corner.rotated_cw(), CoordXY::new(max, max).unwrap());
+    }
+    #[test]
+    fn maze_rotated_cw() {
+        let mut maze = Maze::load_from_str(MAZE_STR).unwrap();
+        maze.goal = CoordXY::new(2, 2).unwrap();
+        maze.set_goal_size(2).unwrap();
+        maze.set_cell_check(CoordXY::new(1, 0).unwrap(), Direction::North, true);
+        maze.set_visited(CoordXY::new(3, 1).unwrap(), true);
+
+        let rotated = maze.rotated_cw();
+        assert_eq!(rotated.validate(), Ok(()));
+        let max = Coord1D::MAX;
+        // The wall north of (1, 0) now lies east of its image
+        let coord = CoordXY::new(0, max - 1).unwrap();
+        assert!(rotated.cell(coord).east());
+        assert!(rotated.cell(coord).check_east());
+        assert!(rotated.is_visited(CoordXY::new(1, max - 3).unwrap()));
+        assert_eq!(rotated.start, CoordXY::new(0, max).unwrap());
+        for goal in maze.goal_cells() {
+            assert!(rotated.is_goal(goal.rotated_cw()));
+        }
+
+        let restored = rotated.rotated_cw().rotated_cw().rotated_cw();
+        assert_eq!(restored.data, maze.data);
+        assert_eq!(restored.start, maze.start);
+        assert_eq!(restored.goal, maze.goal);
+        assert_eq!(restored.visited, maze.visited);
+    }
+    #[test]
+    fn maze_sense() {
+        let mut maze = Maze::load_from_str(MAZE_STR).unwrap();
+        maze.set_cell_check(CoordXY::new(0, 0).unwrap(), Direction::North, true);
+        maze.set_visited(CoordXY::new(0, 0).unwrap(), true);
+        maze.assume_walls_unknown();
+        let fresh = Maze::new(maze.start, maze.goal);
+        assert_eq!(maze.data, fresh.data);
+        assert!(!maze.is_visited(CoordXY::new(0, 0).unwrap()));
+
+        let coord = CoordXY::new(1, 1).unwrap();
+        assert!(!maze.is_explored(coord));
+        maze.sense(coord, [true, false, false, true]);
+        assert!(maze.is_explored(coord));
+        assert!(maze.cell(coord).north());
+        assert!(!maze.cell(coord).east());
+        assert!(maze.cell(CoordXY::new(0, 1).unwrap()).east());
+
+        // The neighbors learn the shared walls the same way as with `set_cell_check`
+        let mut expected = Maze::new(maze.start, maze.goal);
+        use Direction::*;
+        for direction in [North, East, South, West] {
+            expected.set_cell_check(coord, direction, true);
+        }
+        for (cell, expected) in maze.data.iter().zip(&expected.data) {
+            assert_eq!(cell.into_bytes()[0] >> 4, expected.into_bytes()[0] >> 4);
+        }
+        assert!(maze.cell(CoordXY::new(1, 2).unwrap()).check_south());
+        assert!(!maze.is_explored(CoordXY::new(1, 2).unwrap()));
+    }
+    #[test]
+    fn maze_sense_perimeter() {
+        let mut maze = Maze::new(CoordXY::new(0, 0).unwrap(), CoordXY::new(1, 1).unwrap());
+        let last = WIDTH as u8 - 1;
+        for (x, y) in [(0, 0), (last, last)] {
+            let coord = CoordXY::new(x, y).unwrap();
+            maze.sense(coord, [false; 4]);
+            assert!(maze.is_explored(coord));
+            assert_eq!(maze.wall_count(coord), 2);
+        }
+        use Direction::*;
+        assert!(maze.cell(CoordXY::new(0, 0).unwrap()).south());
+        assert!(maze.cell(CoordXY::new(0, 0).unwrap()).west());
+        assert!(maze.cell(CoordXY::new(last, last).unwrap()).north());
+        assert!(maze.cell(CoordXY::new(last, last).unwrap()).east());
+        assert_eq!(
+            maze.open_directions(CoordXY::new(0, 0).unwrap()),
+            [North, East]
+        );
+    }
+    #[test]
+    fn direction_all() {
+        assert_eq!(Direction::ALL.len(), 4);
+        for direction in Direction::iter() {
+            assert_eq!(Direction::iter().filter(|&d| d == direction).count(), 1);
+        }
+    }
+    #[test]
+    fn direction_rotated() {
+        assert_eq!(Direction::North.rotated_cw(), Direction::East);
+        assert_eq!(Direction::North.rotated_ccw(), Direction::West);
+        for direction in Direction::iter() {
+            assert_eq!(direction.rotated_cw().rotated_ccw(), direction);
+            assert_eq!(direction.rotated_cw().rotated_cw(), direction.inverted());
+            let vector = VectorXY::from(direction);
+            assert_eq!(VectorXY::from(direction.rotated_cw()), vector.rotated_cw());
+        }
+    }
+    #[test]
+    fn vector_xy_arithmetic() {
+        let a = VectorXY { x: 2, y: -3 };
+        let b = VectorXY { x: -1, y: 5 };
+        assert_eq!(a + b, VectorXY { x: 1, y: 2 });
+        assert_eq!(a - b, VectorXY { x: 3, y: -8 });
+        assert_eq!(-a, VectorXY { x: -2, y: 3 });
+        assert_eq!(a.scaled(3), VectorXY { x: 6, y: -9 });
+        assert_eq!(a.manhattan_len(), 5);
+        assert_eq!(a.rotated_cw(), VectorXY { x: -3, y: -2 });
+        assert_eq!(a.rotated_cw().rotated_cw(), -a);
+        assert_eq!(
+            VectorXY::from(Direction::North).rotated_cw(),
+            Direction::East.into()
+        );
+    }
+    #[test]
+    fn vector_xy_saturates() {
+        let big = VectorXY {
+            x: i8::MAX,
+            y: i8::MIN,
+        };
+        assert_eq!(big + VectorXY { x: 1, y: -1 }, big);
+        assert_eq!(big - VectorXY { x: -1, y: 1 }, big);
+        assert_eq!(
+            -big,
+            VectorXY {
+                x: -i8::MAX,
+                y: i8::MAX
+            }
+        );
+        assert_eq!(VectorXY { x: 100, y: -100 }.scaled(2), big);
+        assert_eq!(big.manhattan_len(), 255);
+        assert_eq!(
+            big.rotated_cw(),
+            VectorXY {
+                x: i8::MIN,
+                y: -i8::MAX
+            }
+        );
+    }
+    #[test]
+    fn agent_state_turns() {
+        let initial = AgentState {
+            location: CoordXY::new(1, 1).unwrap(),
+            local_location: CellLocalLocation::Center,
+            heading_vector: Direction::North.into(),
+        };
+        let mut state = initial;
+        state.turn_left();
+        assert_eq!(state.heading_vector, Direction::West.into());
+        for _ in 0..3 {
+            state.turn_left();
+        }
+        assert_eq!(state, initial);
+        state.turn_right();
+        assert_eq!(state.heading_vector, Direction::East.into());
+        state.turn_around();
+        assert_eq!(state.heading_vector, Direction::West.into());
+        assert_eq!(state.location, initial.location);
+    }
+    #[test]
+    fn agent_state_step_forward() {
+        let mut state = AgentState {
+            location: CoordXY::new(1, 0).unwrap(),
+            local_location: CellLocalLocation::Center,
+            heading_vector: Direction::West.into(),
+        };
+        state = state.step_forward().unwrap();
+        assert_eq!(state.location, CoordXY::new(0, 0).unwrap());
+        assert_eq!(state.heading_vector, Direction::West.into());
+        assert_eq!(state.step_forward().err(), Some(Error::OutOfRange));
+        state.turn_left();
+        assert_eq!(state.step_forward().err(), Some(Error::OutOfRange));
+        state.turn_around();
+        assert_eq!(
+            state.step_forward().unwrap().location,
+            CoordXY::new(0, 1).unwrap()
+        );
+    }
+    #[test]
+    fn maze_open_directions() {
+        let maze = Maze::load_from_str(MAZE_STR).unwrap();
+        use Direction::*;
+        let coord = |x, y| CoordXY::new(x, y).unwrap();
+        // Dead ends on either side of the wall in the bottom row
+        assert_eq!(maze.open_directions(coord(0, 0)), [East]);
+        assert_eq!(maze.wall_count(coord(0, 0)), 3);
+        assert_eq!(maze.open_directions(coord(1, 0)), [West]);
+        assert_eq!(maze.wall_count(coord(1, 0)), 3);
+        assert_eq!(maze.open_directions(coord(2, 0)), [North, East]);
+        assert_eq!(maze.wall_count(coord(2, 0)), 2);
+        assert_eq!(maze.open_directions(coord(1, 1)), [North, East, West]);
+        assert_eq!(maze.wall_count(coord(1, 1)), 1);
+        assert_eq!(
+            maze.open_directions(coord(2, 2)),
+            [North, East, South, West]
+        );
+        assert_eq!(maze.wall_count(coord(2, 2)), 0);
+    }
+    #[test]
+    fn maze_fill_dead_ends() {
+        let mut maze = Maze::load_from_str(MAZE_STR).unwrap();
+        maze.start = CoordXY::new(2, 0).unwrap();
+        maze.goal = CoordXY::new(3, 3).unwrap();
+        maze.fill_dead_ends();
+        // The pocket left of the start is filled up cell by cell
+        assert_eq!(maze.wall_count(CoordXY::new(1, 0).unwrap()), 4);
+        assert_eq!(maze.wall_count(CoordXY::new(0, 0).unwrap()), 4);
+        assert_eq!(maze.wall_count(CoordXY::new(2, 0).unwrap()), 2);
+        assert_eq!(maze.wall_count(CoordXY::new(1, 1).unwrap()), 1);
+        assert!(maze.is_reachable(maze.start, maze.goal));
+        assert_eq!(maze.validate(), Ok(()));
+
+        // In a perfect maze only the route from the start to the goal remains
+        let (start, goal) = (CoordXY::new(0, 0).unwrap(), CoordXY::new(7, 7).unwrap());
+        let generate = || {
+            let mut rng = rand_pcg::Pcg32::new(0xcafef00dd15ea5e5, 0xa02bdbf7bb3c0a7);
+            crate::generator::generate_backtracker(&mut rng, start, goal)
+        };
+        let route_len = |maze: Maze| {
+            let graph = crate::graph::four_way_grid::Graph { maze };
+            let node =
+                |coord| crate::graph::four_way_grid::Graph::node_index_by_coord_xy(coord).unwrap();
+            crate::solver::astar::astar(&graph, node(start), node(goal))
+                .unwrap()
+                .nodes()
+                .len()
+        };
+        let mut maze = generate();
+        maze.fill_dead_ends();
+        assert!(maze.is_reachable(start, goal));
+        let mut open_cells = 0;
+        for i in 0..WIDTH * WIDTH {
+            let coord = CoordXY::new((i % WIDTH) as u8, (i / WIDTH) as u8).unwrap();
+            if maze.wall_count(coord) < 4 {
+                open_cells += 1;
+            }
+            if coord != start && coord != goal {
+                assert_ne!(maze.wall_count(coord), 3);
+            }
+        }
+        let len = route_len(maze);
+        assert_eq!(open_cells, len);
+        assert_eq!(route_len(generate()), len);
+    }
 }